@@ -0,0 +1,290 @@
+//! Command line interface.
+
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use clap::{ArgAction, Args, Parser, Subcommand, ValueHint};
+use log::{error, LevelFilter};
+#[cfg(unix)]
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::config::serde_utils::SerdeReplace;
+use crate::config::{UiConfig, LOG_TARGET_CONFIG};
+use crate::logging::LogFormat;
+
+/// CLI options for the main Alacritty executable.
+#[derive(Parser, Default, Debug)]
+#[clap(author, about, version = env!("VERSION"))]
+pub struct Options {
+    /// Specify alternative configuration file [default:
+    /// $XDG_CONFIG_HOME/alacritty/alacritty.toml].
+    #[cfg(not(any(target_os = "macos", windows)))]
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub config_file: Option<PathBuf>,
+
+    /// Specify alternative configuration file [default: %APPDATA%\alacritty\alacritty.toml].
+    #[cfg(windows)]
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub config_file: Option<PathBuf>,
+
+    /// Specify alternative configuration file [default: $HOME/.config/alacritty/alacritty.toml].
+    #[cfg(target_os = "macos")]
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub config_file: Option<PathBuf>,
+
+    /// Options which can be passed via IPC.
+    #[clap(flatten)]
+    pub window_options: WindowOptions,
+
+    /// Reduces the level of verbosity (the min level is -qq).
+    #[clap(short, conflicts_with("verbose"), action = ArgAction::Count)]
+    quiet: u8,
+
+    /// Increases the level of verbosity (the max level is -vvv).
+    #[clap(short, conflicts_with("quiet"), action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Format of the log output.
+    #[clap(long, value_enum, default_value_t)]
+    pub log_format: LogFormat,
+
+    /// Path for IPC socket creation.
+    #[cfg(unix)]
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub socket: Option<PathBuf>,
+
+    /// Subcommand passed to the CLI.
+    #[clap(subcommand)]
+    pub subcommands: Option<Subcommands>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::parse()
+    }
+
+    /// Logging filter level.
+    pub fn log_level(&self) -> LevelFilter {
+        match (self.quiet, self.verbose) {
+            // Force quiet mode if any quiet flag is specified.
+            (1, _) => LevelFilter::Error,
+            (2.., _) => LevelFilter::Off,
+
+            // Default.
+            (_, 0) => LevelFilter::Warn,
+
+            // Verbose.
+            (_, 1) => LevelFilter::Info,
+            (_, 2) => LevelFilter::Debug,
+            (0, _) => LevelFilter::Trace,
+        }
+    }
+}
+
+/// Window creation options.
+#[cfg_attr(unix, derive(Serialize, Deserialize))]
+#[derive(Args, Default, Debug, Clone, PartialEq, Eq)]
+pub struct WindowOptions {
+    /// Defines the window title [default: Alacritty Simplified].
+    #[clap(short = 'T', short_alias('t'), long)]
+    pub title: Option<String>,
+
+    /// Override configuration file options [example: 'window.opacity=0.8'].
+    #[clap(short = 'o', long, num_args = 1..)]
+    pub option: Vec<String>,
+
+    /// Apply a configuration profile [example: 'work'].
+    #[clap(long)]
+    pub profile: Option<String>,
+}
+
+impl WindowOptions {
+    /// Parse the configuration overrides for this window.
+    pub fn config_overrides(&self) -> ParsedOptions {
+        let mut overrides = ParsedOptions::from_options(&self.option);
+        overrides.profile = self.profile.clone();
+
+        // Treat the title as an ordinary override, so it survives config reloads.
+        if let Some(title) = &self.title {
+            let mut window = toml::Table::new();
+            window.insert("title".into(), Value::String(title.clone()));
+            let mut table = toml::Table::new();
+            table.insert("window".into(), Value::Table(window));
+            overrides.push((format!("window.title={title:?}"), Value::Table(table)));
+        }
+
+        overrides
+    }
+}
+
+/// Available CLI subcommands.
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    #[cfg(unix)]
+    Msg(MessageOptions),
+
+    /// Print information about the environment for bug reports.
+    DebugInfo,
+}
+
+/// Send a message to the Alacritty socket.
+#[cfg(unix)]
+#[derive(Args, Debug)]
+pub struct MessageOptions {
+    /// IPC socket connection path override.
+    #[clap(short, long, value_hint = ValueHint::FilePath)]
+    pub socket: Option<PathBuf>,
+
+    /// Message which should be sent.
+    #[clap(subcommand)]
+    pub message: SocketMessage,
+}
+
+/// Available socket messages.
+#[cfg(unix)]
+#[derive(Subcommand, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SocketMessage {
+    /// Create a new window in the same Alacritty process.
+    CreateWindow(WindowOptions),
+
+    /// Update the Alacritty configuration.
+    Config(IpcConfig),
+
+    /// Capture the next frames of a window to PNG files.
+    CaptureFrames(CaptureOptions),
+}
+
+/// Update the Alacritty configuration.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcConfig {
+    /// Configuration file options [example: 'window.title="Alacritty"'].
+    #[clap(required_unless_present = "reset", value_name = "CONFIG_OPTIONS")]
+    pub options: Vec<String>,
+
+    /// Window ID for the new config.
+    ///
+    /// Use `-1` to apply this change to all windows.
+    #[clap(short, long, allow_hyphen_values = true, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+
+    /// Clear all runtime configuration changes.
+    #[clap(short, long, conflicts_with = "options")]
+    pub reset: bool,
+}
+
+/// Capture frames for debugging.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CaptureOptions {
+    /// Directory the frames and their event timeline are written to.
+    #[clap(value_hint = ValueHint::DirPath)]
+    pub directory: PathBuf,
+
+    /// Number of frames to capture.
+    #[clap(short, long, default_value_t = 10)]
+    pub count: usize,
+
+    /// Window ID of the captured window.
+    ///
+    /// Use `-1` to capture all windows.
+    #[clap(short, long, allow_hyphen_values = true, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parsed config overrides.
+#[derive(Debug, Default)]
+pub struct ParsedOptions {
+    config_options: Vec<(String, Value)>,
+
+    /// Configuration profile applied before the other options.
+    profile: Option<String>,
+}
+
+impl ParsedOptions {
+    /// Parse config overrides.
+    pub fn from_options(options: &[String]) -> Self {
+        let mut config_options = Vec::new();
+
+        for option in options {
+            let parsed = match toml::from_str(option) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    error!("Ignoring invalid config option '{option}': {err}");
+                    continue;
+                },
+            };
+            config_options.push((option.clone(), parsed));
+        }
+
+        Self { config_options, profile: None }
+    }
+
+    /// Apply config overrides, removing broken ones.
+    pub fn override_config(&mut self, config: &mut UiConfig) {
+        // Apply the profile first, so explicit options take precedence.
+        if let Some(profile) = &self.profile {
+            match config.profile.get(profile).cloned() {
+                Some(options) => {
+                    if let Err(err) = config.replace(options) {
+                        error!(
+                            target: LOG_TARGET_CONFIG,
+                            "Unable to apply profile '{}': {}", profile, err
+                        );
+                    }
+                },
+                None => error!(
+                    target: LOG_TARGET_CONFIG,
+                    "Configuration profile '{}' does not exist", profile
+                ),
+            }
+        }
+
+        let mut i = 0;
+        while i < self.config_options.len() {
+            let (option, parsed) = &self.config_options[i];
+            match config.replace(parsed.clone()) {
+                Err(err) => {
+                    error!("Unable to override option '{}': {}", option, err);
+                    self.config_options.swap_remove(i);
+                },
+                Ok(_) => i += 1,
+            }
+        }
+    }
+
+    /// Name of the configuration profile applied by these options.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Apply config overrides to a CoW config.
+    pub fn override_config_rc(&mut self, config: Rc<UiConfig>) -> Rc<UiConfig> {
+        // Skip clone without write requirement.
+        if self.config_options.is_empty() && self.profile.is_none() {
+            return config;
+        }
+
+        // Override cloned config.
+        let mut config = (*config).clone();
+        self.override_config(&mut config);
+
+        Rc::new(config)
+    }
+}
+
+impl Deref for ParsedOptions {
+    type Target = Vec<(String, Value)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.config_options
+    }
+}
+
+impl DerefMut for ParsedOptions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.config_options
+    }
+}
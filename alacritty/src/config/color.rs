@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use winit::window::Theme;
+
+use crate::config::serde_utils::impl_replace_struct;
+use crate::display::Rgb;
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Colors {
+    pub primary: PrimaryColors,
+    pub normal: NormalColors,
+    pub theme: ColorSchemes,
+    pub transparent_background_colors: bool,
+}
+
+impl_replace_struct!(Colors { primary, normal, theme, transparent_background_colors });
+
+/// Color scheme files for the light and dark system theme.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ColorSchemes {
+    pub light: Option<PathBuf>,
+    pub dark: Option<PathBuf>,
+}
+
+impl_replace_struct!(ColorSchemes { light, dark });
+
+impl ColorSchemes {
+    /// Check if any color scheme is configured.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.light.is_none() && self.dark.is_none()
+    }
+
+    /// Color scheme for a system theme.
+    #[inline]
+    pub fn get(&self, theme: Theme) -> Option<&PathBuf> {
+        match theme {
+            Theme::Light => self.light.as_ref(),
+            Theme::Dark => self.dark.as_ref(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct PrimaryColors {
+    pub foreground: Rgb,
+    pub background: Rgb,
+}
+
+impl_replace_struct!(PrimaryColors { foreground, background });
+
+impl Default for PrimaryColors {
+    fn default() -> Self {
+        PrimaryColors {
+            foreground: Rgb::new(0xd8, 0xd8, 0xd8),
+            background: Rgb::new(0x18, 0x18, 0x18),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct NormalColors {
+    pub black: Rgb,
+    pub red: Rgb,
+    pub green: Rgb,
+    pub yellow: Rgb,
+    pub blue: Rgb,
+    pub magenta: Rgb,
+    pub cyan: Rgb,
+    pub white: Rgb,
+}
+
+impl_replace_struct!(NormalColors { black, red, green, yellow, blue, magenta, cyan, white });
+
+impl Default for NormalColors {
+    fn default() -> Self {
+        NormalColors {
+            black: Rgb::new(0x18, 0x18, 0x18),
+            red: Rgb::new(0xac, 0x42, 0x42),
+            green: Rgb::new(0x90, 0xa9, 0x59),
+            yellow: Rgb::new(0xf4, 0xbf, 0x75),
+            blue: Rgb::new(0x6a, 0x9f, 0xb5),
+            magenta: Rgb::new(0xaa, 0x75, 0x9f),
+            cyan: Rgb::new(0x75, 0xb5, 0xaa),
+            white: Rgb::new(0xd8, 0xd8, 0xd8),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use serde::Deserialize;
+
+use crate::config::serde_utils::impl_replace_struct;
+
+/// Debugging options.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Debug {
+    /// Should show render timer.
+    pub render_timer: bool,
+}
+
+impl_replace_struct!(Debug { render_timer });
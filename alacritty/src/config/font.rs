@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crossfont::Size as FontSize;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::config::serde_utils::{impl_replace_struct, impl_replace_value};
+use crate::config::ui_config::Delta;
+
+/// Font config.
+///
+/// Defaults are provided at the level of this struct per platform specific
+/// implementation.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Font {
+    /// Extra spacing per character.
+    pub offset: Delta<i8>,
+
+    /// Glyph offset within character cell.
+    pub glyph_offset: Delta<i8>,
+
+    /// Normal font face.
+    normal: FontDescription,
+
+    /// Font size in points.
+    size: Size,
+}
+
+impl_replace_struct!(Font { offset, glyph_offset, normal, size });
+
+impl Font {
+    /// Get a font clone with a size modification.
+    pub fn with_size(self, size: FontSize) -> Font {
+        Font { size: Size(size), ..self }
+    }
+
+    #[inline]
+    pub fn size(&self) -> FontSize {
+        self.size.0
+    }
+
+    /// Get normal font description.
+    pub fn normal(&self) -> &FontDescription {
+        &self.normal
+    }
+}
+
+/// Description of the normal font.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct FontDescription {
+    pub family: String,
+    pub style: Option<String>,
+}
+
+impl_replace_struct!(FontDescription { family, style });
+
+impl Default for FontDescription {
+    fn default() -> FontDescription {
+        FontDescription {
+            #[cfg(not(any(target_os = "macos", windows)))]
+            family: "monospace".into(),
+            #[cfg(target_os = "macos")]
+            family: "Menlo".into(),
+            #[cfg(windows)]
+            family: "Consolas".into(),
+            style: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Size(FontSize);
+
+impl_replace_value!(Size);
+
+impl Default for Size {
+    fn default() -> Self {
+        Self(FontSize::new(11.25))
+    }
+}
+
+impl<'de> Deserialize<'de> for Size {
+    fn deserialize<D>(deserializer: D) -> Result<Size, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NumVisitor;
+        impl<'v> Visitor<'v> for NumVisitor {
+            type Value = Size;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("f64 or i64")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                Ok(Size(FontSize::new(value as f32)))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                Ok(Size(FontSize::new(value as f32)))
+            }
+        }
+
+        deserializer.deserialize_any(NumVisitor)
+    }
+}
@@ -0,0 +1,563 @@
+//! Configuration file loading.
+
+#[cfg(not(windows))]
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::result::Result as StdResult;
+use std::{fs, io};
+
+use log::{debug, error, info};
+use serde::Deserialize;
+use toml::de::Error as TomlError;
+use toml::{Table, Value};
+use winit::window::Theme;
+
+pub mod color;
+pub mod debug;
+pub mod font;
+pub mod monitor;
+pub mod serde_utils;
+pub mod substitution;
+pub mod ui_config;
+pub mod window;
+
+use crate::cli::Options;
+use crate::config::serde_utils::SerdeReplace;
+pub use crate::config::ui_config::UiConfig;
+
+/// Logging target for config error messages.
+pub const LOG_TARGET_CONFIG: &str = "alacritty::config";
+
+/// Maximum number of depth for the configuration file imports.
+pub const IMPORT_RECURSION_LIMIT: usize = 5;
+
+/// Result from config loading.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors occurring during config loading.
+#[derive(Debug)]
+pub enum Error {
+    /// Config file not found.
+    NotFound,
+
+    /// io error reading file.
+    Io(io::Error),
+
+    /// Invalid toml.
+    Toml(TomlError),
+
+    /// Config file imports itself, either directly or through other imports.
+    ImportCycle(Vec<PathBuf>),
+
+    /// Imports are nested deeper than [`IMPORT_RECURSION_LIMIT`].
+    ImportDepth(Vec<PathBuf>),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::NotFound => None,
+            Error::Io(err) => err.source(),
+            Error::Toml(err) => err.source(),
+            Error::ImportCycle(_) | Error::ImportDepth(_) => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "Unable to locate config file"),
+            Error::Io(err) => write!(f, "Error reading config file: {}", err),
+            Error::Toml(err) => write!(f, "Config error: {}", err),
+            Error::ImportCycle(chain) => {
+                write!(f, "Config import cycle detected:{}", ImportChain(chain))
+            },
+            Error::ImportDepth(chain) => write!(
+                f,
+                "Exceeded maximum configuration import depth of {}:{}",
+                IMPORT_RECURSION_LIMIT,
+                ImportChain(chain)
+            ),
+        }
+    }
+}
+
+/// Formatter for the list of files which lead to an import error.
+struct ImportChain<'a>(&'a [PathBuf]);
+
+impl<'a> Display for ImportChain<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, path) in self.0.iter().enumerate() {
+            let arrow = if i == 0 { "" } else { "-> " };
+            write!(f, "\n  {}{:?}", arrow, path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Self {
+        if val.kind() == io::ErrorKind::NotFound {
+            Error::NotFound
+        } else {
+            Error::Io(val)
+        }
+    }
+}
+
+impl From<TomlError> for Error {
+    fn from(val: TomlError) -> Self {
+        Error::Toml(val)
+    }
+}
+
+/// Load the configuration file.
+pub fn load(options: &Options) -> UiConfig {
+    let config_path = options.config_file.clone().or_else(|| installed_config("toml"));
+
+    // Load the config using the following fallback behavior:
+    //  - Config path
+    //  - Default
+    config_path.as_ref().and_then(|config_path| load_from(config_path, true).ok()).unwrap_or_else(
+        || {
+            let mut config = UiConfig::default();
+            match config_path {
+                Some(config_path) => config.config_paths.push(config_path),
+                None => info!("No config file found; using default"),
+            }
+            config
+        },
+    )
+}
+
+/// Load all configuration options which differ from the defaults.
+///
+/// This merges the configuration files and CLI overrides without applying them to the defaults.
+pub fn load_changes(config: &UiConfig, options: &Options) -> Value {
+    // The root configuration file is always the first path.
+    let mut changes = match config.config_paths.first() {
+        Some(path) => match parse_config(path, &mut Vec::new(), &mut Vec::new()) {
+            Ok(changes) => changes,
+            Err(err) => {
+                error!(target: LOG_TARGET_CONFIG, "Unable to load config {:?}: {}", path, err);
+                Value::Table(Table::new())
+            },
+        },
+        None => Value::Table(Table::new()),
+    };
+
+    // Apply the selected profile as if it was part of the configuration file.
+    //
+    // The profile is taken from the parsed file instead of the loaded config, so references to
+    // secrets are printed without being resolved.
+    let profile = options.window_options.profile.as_ref().and_then(|profile| {
+        changes.get("profile").and_then(|profiles| profiles.get(profile)).cloned()
+    });
+    if let Some(profile) = profile {
+        changes = serde_utils::merge(changes, profile);
+    }
+
+    for (_, value) in options.window_options.config_overrides().iter() {
+        changes = serde_utils::merge(changes, value.clone());
+    }
+
+    changes
+}
+
+/// Attempt to reload the configuration file.
+pub fn reload(config_path: &Path) -> Result<UiConfig> {
+    debug!("Reloading configuration file: {:?}", config_path);
+
+    // Load config, propagating errors.
+    load_from(config_path, false)
+}
+
+/// Load configuration file and log errors.
+///
+/// Commands referenced by the configuration are only run with `run_commands`.
+fn load_from(path: &Path, run_commands: bool) -> Result<UiConfig> {
+    match read_config(path, run_commands) {
+        Ok(config) => Ok(config),
+        Err(err) => {
+            error!(target: LOG_TARGET_CONFIG, "Unable to load config {:?}: {}", path, err);
+            Err(err)
+        },
+    }
+}
+
+/// Deserialize configuration file from path.
+fn read_config(path: &Path, run_commands: bool) -> Result<UiConfig> {
+    let mut config_paths = Vec::new();
+    let mut config_value = parse_config(path, &mut config_paths, &mut Vec::new())?;
+
+    // Resolve references to external values.
+    substitution::substitute(&mut config_value, run_commands);
+
+    // Deserialize to concrete type.
+    let mut config = UiConfig::deserialize(config_value)?;
+    config.config_paths = config_paths;
+
+    // Watch color schemes for changes.
+    let schemes = [&config.colors.theme.light, &config.colors.theme.dark];
+    for path in schemes.into_iter().flatten().map(|path| resolve_home(path.clone())) {
+        if !config.config_paths.contains(&path) {
+            config.config_paths.push(path);
+        }
+    }
+
+    Ok(config)
+}
+
+/// Apply the color scheme of a system theme and the options of a window to a config.
+///
+/// The scheme is selected from the config with `apply_options` applied, so options can change
+/// it. Its colors replace the colors of the configuration files, but `apply_options` is applied
+/// again afterwards, so colors set by options take precedence over the scheme.
+pub fn with_theme_colors<F>(
+    config: Rc<UiConfig>,
+    theme: Option<Theme>,
+    mut apply_options: F,
+) -> Rc<UiConfig>
+where
+    F: FnMut(Rc<UiConfig>) -> Rc<UiConfig>,
+{
+    let options_config = apply_options(config.clone());
+
+    let path = match theme.and_then(|theme| options_config.colors.theme.get(theme)) {
+        Some(path) => resolve_home(path.clone()),
+        None => return options_config,
+    };
+
+    let scheme = match load_color_scheme(&path) {
+        Ok(scheme) => scheme,
+        Err(err) => {
+            error!(target: LOG_TARGET_CONFIG, "Unable to load color scheme {:?}: {}", path, err);
+            return options_config;
+        },
+    };
+
+    let mut config = config;
+    if let Err(err) = Rc::make_mut(&mut config).colors.replace(scheme) {
+        error!(target: LOG_TARGET_CONFIG, "Invalid color scheme {:?}: {}", path, err);
+        return options_config;
+    }
+
+    apply_options(config)
+}
+
+/// Load the colors of a color scheme file.
+fn load_color_scheme(path: &Path) -> Result<Value> {
+    let mut colors = match deserialize_config(path)?.get("colors") {
+        Some(Value::Table(colors)) => colors.clone(),
+        _ => Table::new(),
+    };
+
+    // Schemes cannot select other schemes.
+    colors.remove("theme");
+
+    Ok(Value::Table(colors))
+}
+
+/// Deserialize all configuration files as generic Value.
+///
+/// The `import_chain` contains all files which are currently being imported, starting with the
+/// root configuration file.
+fn parse_config(
+    path: &Path,
+    config_paths: &mut Vec<PathBuf>,
+    import_chain: &mut Vec<PathBuf>,
+) -> Result<Value> {
+    // Resolve symlinks, so the same file is detected no matter how it was referenced.
+    let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+
+    // Refuse to load a file which is already part of the current import chain.
+    if import_chain.contains(&canonical_path) {
+        let mut chain = import_chain.clone();
+        chain.push(canonical_path);
+        return Err(Error::ImportCycle(chain));
+    }
+
+    // Limit nesting, to avoid excessive recursion through long import chains.
+    if import_chain.len() > IMPORT_RECURSION_LIMIT {
+        let mut chain = import_chain.clone();
+        chain.push(canonical_path);
+        return Err(Error::ImportDepth(chain));
+    }
+
+    if !config_paths.iter().any(|config_path| config_path == path) {
+        config_paths.push(path.to_owned());
+    }
+
+    // Deserialize the configuration file.
+    let config = deserialize_config(path)?;
+
+    // Merge config with imports.
+    import_chain.push(canonical_path);
+    let imports = load_imports(&config, config_paths, import_chain);
+    import_chain.pop();
+
+    Ok(serde_utils::merge(imports, config))
+}
+
+/// Deserialize a configuration file.
+pub fn deserialize_config(path: &Path) -> Result<Value> {
+    let mut contents = fs::read_to_string(path)?;
+
+    // Remove UTF-8 BOM.
+    if contents.starts_with('\u{FEFF}') {
+        contents = contents.split_off(3);
+    }
+
+    // Load configuration file as Value.
+    let config: Value = toml::from_str(&contents)?;
+
+    Ok(config)
+}
+
+/// Load all referenced configuration files.
+fn load_imports(
+    config: &Value,
+    config_paths: &mut Vec<PathBuf>,
+    import_chain: &mut Vec<PathBuf>,
+) -> Value {
+    // Get paths for all imports.
+    let import_paths = match imports(config) {
+        Ok(import_paths) => import_paths,
+        Err(err) => {
+            error!(target: LOG_TARGET_CONFIG, "{err}");
+            return Value::Table(Table::new());
+        },
+    };
+
+    // Parse configs for all imports recursively.
+    let mut merged = Value::Table(Table::new());
+    for import_path in import_paths {
+        let path = match import_path {
+            Ok(path) => path,
+            Err(err) => {
+                error!(target: LOG_TARGET_CONFIG, "{err}");
+                continue;
+            },
+        };
+
+        if !path.exists() {
+            info!("Config import not found:\n  {:?}", path.display());
+            continue;
+        }
+
+        match parse_config(&path, config_paths, import_chain) {
+            Ok(config) => merged = serde_utils::merge(merged, config),
+            Err(err) => {
+                error!(target: LOG_TARGET_CONFIG, "Unable to import config {:?}: {}", path, err)
+            },
+        }
+    }
+
+    merged
+}
+
+/// Get all import paths for a configuration.
+pub fn imports(config: &Value) -> StdResult<Vec<StdResult<PathBuf, String>>, String> {
+    let imports = match config.get("import") {
+        Some(imports) => imports,
+        None => return Ok(Vec::new()),
+    };
+
+    let imports = match imports.as_array() {
+        Some(array) => array,
+        None => return Err("Invalid import type: expected a sequence".into()),
+    };
+
+    let mut import_paths = Vec::new();
+
+    for import in imports {
+        let path = match import.as_str() {
+            Some(path) => PathBuf::from(path),
+            None => {
+                import_paths.push(Err("Invalid import element type: expected path string".into()));
+                continue;
+            },
+        };
+
+        import_paths.push(Ok(resolve_home(path)));
+    }
+
+    Ok(import_paths)
+}
+
+/// Resolve paths relative to user's home directory.
+fn resolve_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~/"), home::home_dir()) {
+        (Ok(stripped), Some(home_dir)) => home_dir.join(stripped),
+        _ => path,
+    }
+}
+
+/// Get the location of the first found default config file paths
+/// according to the following order:
+///
+/// 1. $XDG_CONFIG_HOME/alacritty/alacritty.toml
+/// 2. $XDG_CONFIG_HOME/alacritty.toml
+/// 3. $HOME/.config/alacritty/alacritty.toml
+/// 4. $HOME/.alacritty.toml
+#[cfg(not(windows))]
+pub fn installed_config(suffix: &str) -> Option<PathBuf> {
+    let file_name = format!("alacritty.{suffix}");
+
+    // Try using XDG location by default.
+    xdg::BaseDirectories::with_prefix("alacritty")
+        .ok()
+        .and_then(|xdg| xdg.find_config_file(&file_name))
+        .or_else(|| {
+            xdg::BaseDirectories::new()
+                .ok()
+                .and_then(|fallback| fallback.find_config_file(&file_name))
+        })
+        .or_else(|| {
+            if let Ok(home) = env::var("HOME") {
+                // Fallback path: $HOME/.config/alacritty/alacritty.toml.
+                let fallback = PathBuf::from(&home).join(".config/alacritty").join(&file_name);
+                if fallback.exists() {
+                    return Some(fallback);
+                }
+                // Fallback path: $HOME/.alacritty.toml.
+                let hidden_name = format!(".{file_name}");
+                let fallback = PathBuf::from(&home).join(hidden_name);
+                if fallback.exists() {
+                    return Some(fallback);
+                }
+            }
+            None
+        })
+}
+
+#[cfg(windows)]
+pub fn installed_config(suffix: &str) -> Option<PathBuf> {
+    let file_name = format!("alacritty.{suffix}");
+    dirs::config_dir().map(|path| path.join("alacritty").join(file_name)).filter(|new| new.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cli::ParsedOptions;
+    use crate::display::Rgb;
+
+    /// Create an empty directory for the configuration files of a test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("alacritty-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    /// Write a configuration file which sets `key` and imports `import`.
+    fn write_config(dir: &Path, name: &str, import: Option<&str>) -> PathBuf {
+        let path = dir.join(name);
+        let import = import.map(|import| dir.join(import)).into_iter().collect::<Vec<_>>();
+        let contents = format!("import = {:?}\n{} = true\n", import, name.replace('.', "_"));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn import_direct_cycle() {
+        let dir = test_dir("import-direct-cycle");
+        let a = write_config(&dir, "a.toml", Some("a.toml"));
+
+        // The cyclic import is skipped, without discarding the file itself.
+        let config = parse_config(&a, &mut Vec::new(), &mut Vec::new()).unwrap();
+        assert_eq!(config.get("a_toml"), Some(&Value::Boolean(true)));
+
+        match parse_config(&a, &mut Vec::new(), &mut vec![a.clone()]) {
+            Err(Error::ImportCycle(chain)) => assert_eq!(chain, vec![a.clone(), a]),
+            result => panic!("expected import cycle, got {result:?}"),
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_indirect_cycle() {
+        let dir = test_dir("import-indirect-cycle");
+        let a = write_config(&dir, "a.toml", Some("b.toml"));
+        let b = write_config(&dir, "b.toml", Some("a.toml"));
+
+        let mut config_paths = Vec::new();
+        let config = parse_config(&a, &mut config_paths, &mut Vec::new()).unwrap();
+        assert_eq!(config.get("a_toml"), Some(&Value::Boolean(true)));
+        assert_eq!(config.get("b_toml"), Some(&Value::Boolean(true)));
+        assert_eq!(config_paths, vec![a.clone(), b.clone()]);
+
+        match parse_config(&a, &mut Vec::new(), &mut vec![a.clone(), b.clone()]) {
+            Err(Error::ImportCycle(chain)) => assert_eq!(chain, vec![a.clone(), b, a]),
+            result => panic!("expected import cycle, got {result:?}"),
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_depth_limit() {
+        let dir = test_dir("import-depth-limit");
+        let names: Vec<_> = (0..=IMPORT_RECURSION_LIMIT + 1).map(|i| format!("{i}.toml")).collect();
+        let paths: Vec<_> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| write_config(&dir, name, names.get(i + 1).map(String::as_str)))
+            .collect();
+
+        // Files nested deeper than the limit are ignored.
+        let config = parse_config(&paths[0], &mut Vec::new(), &mut Vec::new()).unwrap();
+        let key = |i: usize| format!("{i}_toml");
+        assert_eq!(config.get(key(IMPORT_RECURSION_LIMIT)), Some(&Value::Boolean(true)));
+        assert_eq!(config.get(key(IMPORT_RECURSION_LIMIT + 1)), None);
+
+        let last = paths.last().unwrap();
+        let mut import_chain = paths[..paths.len() - 1].to_vec();
+        match parse_config(last, &mut Vec::new(), &mut import_chain) {
+            Err(Error::ImportDepth(chain)) => assert_eq!(&chain, &paths),
+            result => panic!("expected import depth error, got {result:?}"),
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn theme_colors_below_options() {
+        let dir = test_dir("theme-colors");
+        let scheme = dir.join("dark.toml");
+        let contents = "[colors.primary]\nforeground = '#111111'\nbackground = '#222222'\n";
+        fs::write(&scheme, contents).unwrap();
+
+        // Colors of the configuration file are replaced by the scheme.
+        let mut config = UiConfig::default();
+        config.colors.primary.background = Rgb::new(0x44, 0x44, 0x44);
+        let config = Rc::new(config);
+
+        // The scheme is selected and partially overridden by options.
+        let mut options = ParsedOptions::from_options(&[
+            format!("colors.theme.dark = {:?}", scheme),
+            String::from("colors.primary.foreground = '#333333'"),
+        ]);
+
+        let dark = with_theme_colors(config.clone(), Some(Theme::Dark), |config| {
+            options.override_config_rc(config)
+        });
+        assert_eq!(dark.colors.primary.foreground, Rgb::new(0x33, 0x33, 0x33));
+        assert_eq!(dark.colors.primary.background, Rgb::new(0x22, 0x22, 0x22));
+
+        // Without a scheme for the theme, only the options are applied.
+        let light = with_theme_colors(config, Some(Theme::Light), |config| {
+            options.override_config_rc(config)
+        });
+        assert_eq!(light.colors.primary.foreground, Rgb::new(0x33, 0x33, 0x33));
+        assert_eq!(light.colors.primary.background, Rgb::new(0x44, 0x44, 0x44));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
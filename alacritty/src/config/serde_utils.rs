@@ -0,0 +1,116 @@
+//! Serde helpers.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use toml::{Table, Value};
+
+/// Replace parts of a config with a partial TOML value.
+///
+/// Structs only replace the fields present in the value and leave all other fields untouched,
+/// while all other types are replaced entirely.
+pub trait SerdeReplace {
+    fn replace(&mut self, value: Value) -> Result<(), Box<dyn Error>>;
+}
+
+/// Implement [`SerdeReplace`] for types which are always replaced as a whole.
+macro_rules! impl_replace_value {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl $crate::config::serde_utils::SerdeReplace for $ty {
+                fn replace(
+                    &mut self,
+                    value: toml::Value,
+                ) -> Result<(), Box<dyn std::error::Error>> {
+                    *self = serde::Deserialize::deserialize(value)?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+pub(crate) use impl_replace_value;
+
+/// Implement [`SerdeReplace`] for a struct, replacing each of the listed fields recursively.
+macro_rules! impl_replace_struct {
+    ($ty:ty { $($(#[$attr:meta])* $field:ident),* $(,)? }) => {
+        impl $crate::config::serde_utils::SerdeReplace for $ty {
+            fn replace(
+                &mut self,
+                value: toml::Value,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                let table = match value {
+                    toml::Value::Table(table) => table,
+                    value => {
+                        return Err(format!("Expected table, found {}", value.type_str()).into())
+                    },
+                };
+
+                for (key, value) in table {
+                    match key.as_str() {
+                        $($(#[$attr])* stringify!($field) => {
+                            $crate::config::serde_utils::SerdeReplace::replace(
+                                &mut self.$field,
+                                value,
+                            )
+                            .map_err(|err| format!("{}: {}", key, err))?
+                        },)*
+                        _ => return Err(format!("Field \"{}\" does not exist", key).into()),
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+pub(crate) use impl_replace_struct;
+
+impl_replace_value!(bool, u8, u16, u32, u64, usize, i8, i16, i32, i64, f32, f64, String);
+impl_replace_value!(HashMap<String, Value>);
+
+impl<T: DeserializeOwned> SerdeReplace for Option<T> {
+    fn replace(&mut self, value: Value) -> Result<(), Box<dyn Error>> {
+        *self = Some(T::deserialize(value)?);
+        Ok(())
+    }
+}
+
+impl<T: DeserializeOwned> SerdeReplace for Vec<T> {
+    fn replace(&mut self, value: Value) -> Result<(), Box<dyn Error>> {
+        *self = Vec::<T>::deserialize(value)?;
+        Ok(())
+    }
+}
+
+/// Merge two serde structures.
+///
+/// This will take all values from `replacement` and use `base` whenever a value isn't present in
+/// `replacement`.
+pub fn merge(base: Value, replacement: Value) -> Value {
+    match (base, replacement) {
+        (Value::Array(mut base), Value::Array(mut replacement)) => {
+            base.append(&mut replacement);
+            Value::Array(base)
+        },
+        (Value::Table(base), Value::Table(replacement)) => {
+            Value::Table(merge_tables(base, replacement))
+        },
+        (_, value) => value,
+    }
+}
+
+/// Merge two key/value tables.
+fn merge_tables(mut base: Table, replacement: Table) -> Table {
+    for (key, value) in replacement {
+        let value = match base.remove(&key) {
+            Some(base_value) => merge(base_value, value),
+            None => value,
+        };
+        base.insert(key, value);
+    }
+
+    base
+}
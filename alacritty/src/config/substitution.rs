@@ -0,0 +1,173 @@
+//! Substitution of external values into configuration strings.
+//!
+//! Strings can reference values which should not be stored in the configuration file:
+//!  - `${env:NAME}` is replaced by the value of the environment variable `NAME`
+//!  - `${cmd:COMMAND}` is replaced by the output of running `COMMAND` in the system shell
+//!
+//! A literal `${` can be written as `$${`.
+//!
+//! Commands are only run when the configuration is first loaded, since they might block while
+//! waiting for user input like a password prompt. Configuration reloads reuse their output.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::process::Command;
+use std::sync::Mutex;
+
+use log::error;
+use toml::Value;
+
+use crate::config::LOG_TARGET_CONFIG;
+
+/// Output of all commands which have been run, keyed by the command.
+static COMMAND_OUTPUT: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Replace all references in the strings of a configuration value.
+///
+/// Commands which have not been run before are only run with `run_commands`.
+pub fn substitute(value: &mut Value, run_commands: bool) {
+    match value {
+        Value::String(string) if string.contains("${") => match resolve(string, run_commands) {
+            Ok(resolved) => *string = resolved,
+            Err(err) => error!(target: LOG_TARGET_CONFIG, "Unable to substitute {string:?}: {err}"),
+        },
+        Value::Array(array) => array.iter_mut().for_each(|value| substitute(value, run_commands)),
+        Value::Table(table) => {
+            table.iter_mut().for_each(|(_, value)| substitute(value, run_commands))
+        },
+        _ => (),
+    }
+}
+
+/// Resolve all references in a string.
+fn resolve(string: &str, run_commands: bool) -> Result<String, String> {
+    let mut resolved = String::with_capacity(string.len());
+    let mut rest = string;
+
+    while let Some(start) = rest.find("${") {
+        // Keep escaped references verbatim, without the escape character.
+        if rest[..start].ends_with('$') {
+            resolved.push_str(&rest[..start - 1]);
+            resolved.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        resolved.push_str(&rest[..start]);
+        rest = &rest[start + 2..];
+
+        let end = rest.find('}').ok_or("unterminated reference")?;
+        resolved.push_str(&resolve_reference(&rest[..end], run_commands)?);
+        rest = &rest[end + 1..];
+    }
+
+    resolved.push_str(rest);
+
+    Ok(resolved)
+}
+
+/// Resolve a single reference, without the surrounding `${` and `}`.
+fn resolve_reference(reference: &str, run_commands: bool) -> Result<String, String> {
+    match reference.split_once(':') {
+        Some(("env", name)) => {
+            env::var(name).map_err(|err| format!("environment variable {name:?}: {err}"))
+        },
+        Some(("cmd", command)) => command_output(command, run_commands),
+        _ => Err(format!("unknown reference {reference:?}")),
+    }
+}
+
+/// Get the output of a command, reusing the output of previous runs.
+fn command_output(command: &str, run_commands: bool) -> Result<String, String> {
+    if let Some(output) = COMMAND_OUTPUT.lock().unwrap().get(command) {
+        return Ok(output.clone());
+    }
+
+    if !run_commands {
+        return Err(format!("command {command:?} is only run on startup"));
+    }
+
+    let output = run_command(command)?;
+    COMMAND_OUTPUT.lock().unwrap().insert(command.into(), output.clone());
+
+    Ok(output)
+}
+
+/// Run a command through the system shell and return its output.
+fn run_command(command: &str) -> Result<String, String> {
+    #[cfg(not(windows))]
+    let output = Command::new("sh").args(["-c", command]).output();
+    #[cfg(windows)]
+    let output = Command::new("cmd").args(["/C", command]).output();
+
+    let output = output.map_err(|err| format!("command {command:?}: {err}"))?;
+    if !output.status.success() {
+        return Err(format!("command {command:?} failed with {}", output.status));
+    }
+
+    let mut stdout = String::from_utf8(output.stdout)
+        .map_err(|_| format!("command {command:?} returned invalid UTF-8"))?;
+
+    // Remove the trailing newline most commands print.
+    if stdout.ends_with('\n') {
+        stdout.pop();
+        if stdout.ends_with('\r') {
+            stdout.pop();
+        }
+    }
+
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_reference() {
+        env::set_var("ALACRITTY_TEST_SUBSTITUTION", "value");
+
+        let resolved = resolve("a ${env:ALACRITTY_TEST_SUBSTITUTION} b", false);
+        assert_eq!(resolved.as_deref(), Ok("a value b"));
+
+        assert!(resolve("${env:ALACRITTY_TEST_SUBSTITUTION_MISSING}", false).is_err());
+    }
+
+    #[test]
+    fn escaped_reference() {
+        assert_eq!(resolve("a $${env:HOME} b", false).as_deref(), Ok("a ${env:HOME} b"));
+        assert_eq!(resolve("$", false).as_deref(), Ok("$"));
+    }
+
+    #[test]
+    fn unterminated_reference() {
+        assert_eq!(resolve("a ${env:HOME", false), Err("unterminated reference".into()));
+    }
+
+    #[test]
+    fn unknown_provider() {
+        assert!(resolve("${file:/etc/hostname}", false).is_err());
+        assert!(resolve("${HOME}", false).is_err());
+    }
+
+    #[test]
+    fn failing_command() {
+        assert!(resolve("${cmd:exit 3}", true).is_err());
+    }
+
+    #[test]
+    fn command_trailing_newline() {
+        assert_eq!(resolve("${cmd:echo output}", true).as_deref(), Ok("output"));
+    }
+
+    #[test]
+    fn command_only_run_on_startup() {
+        let reference = "${cmd:echo startup}";
+
+        assert!(resolve(reference, false).is_err());
+        assert_eq!(resolve(reference, true).as_deref(), Ok("startup"));
+
+        // Reloads reuse the previous output.
+        assert_eq!(resolve(reference, false).as_deref(), Ok("startup"));
+    }
+}
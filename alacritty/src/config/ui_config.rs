@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use toml::Value;
+
+use crate::config::color::Colors;
+use crate::config::debug::Debug;
+use crate::config::font::Font;
+use crate::config::serde_utils::{impl_replace_struct, impl_replace_value};
+use crate::config::window::WindowConfig;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Font configuration.
+    pub font: Font,
+
+    /// Window configuration.
+    pub window: WindowConfig,
+
+    /// RGB values for colors.
+    pub colors: Colors,
+
+    /// Debug options.
+    pub debug: Debug,
+
+    /// Live config reload.
+    pub live_config_reload: bool,
+
+    /// Offer IPC through a unix socket.
+    #[cfg(unix)]
+    pub ipc_socket: bool,
+
+    /// Named sets of options which can be applied on top of the configuration.
+    pub profile: HashMap<String, Value>,
+
+    /// Path where config was loaded from.
+    #[serde(skip)]
+    pub config_paths: Vec<PathBuf>,
+}
+
+impl_replace_struct!(UiConfig {
+    font,
+    window,
+    colors,
+    debug,
+    live_config_reload,
+    #[cfg(unix)]
+    ipc_socket,
+    profile,
+});
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            live_config_reload: true,
+            #[cfg(unix)]
+            ipc_socket: true,
+            font: Default::default(),
+            window: Default::default(),
+            colors: Default::default(),
+            debug: Default::default(),
+            profile: Default::default(),
+            config_paths: Default::default(),
+        }
+    }
+}
+
+impl UiConfig {
+    #[inline]
+    pub fn window_opacity(&self) -> f32 {
+        self.window.opacity.as_f32()
+    }
+
+    /// Opacity of non-default cell backgrounds.
+    ///
+    /// Text itself is always drawn fully opaque.
+    #[inline]
+    pub fn cell_background_opacity(&self) -> f32 {
+        if self.colors.transparent_background_colors {
+            self.window_opacity()
+        } else {
+            1.
+        }
+    }
+}
+
+/// A delta for a point in a 2 dimensional plane.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Delta<T: Default> {
+    /// Horizontal change.
+    pub x: T,
+    /// Vertical change.
+    pub y: T,
+}
+
+impl_replace_struct!(Delta<i8> { x, y });
+impl_replace_struct!(Delta<u8> { x, y });
+impl_replace_struct!(Delta<i32> { x, y });
+
+/// A clamped value between 0.0 and 1.0.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(from = "f32")]
+pub struct Percentage(f32);
+
+impl Default for Percentage {
+    fn default() -> Self {
+        Percentage(1.0)
+    }
+}
+
+impl Percentage {
+    pub fn new(value: f32) -> Self {
+        Percentage(value.clamp(0., 1.))
+    }
+
+    pub fn as_f32(self) -> f32 {
+        self.0
+    }
+}
+
+impl_replace_value!(Percentage);
+
+impl From<f32> for Percentage {
+    fn from(value: f32) -> Self {
+        Percentage::new(value)
+    }
+}
@@ -0,0 +1,173 @@
+use serde::Deserialize;
+#[cfg(target_os = "macos")]
+use winit::platform::macos::OptionAsAlt as WinitOptionAsAlt;
+use winit::window::Fullscreen;
+
+use crate::config::serde_utils::{impl_replace_struct, impl_replace_value};
+use crate::config::ui_config::{Delta, Percentage};
+
+/// Default Alacritty name, used for window title and class.
+pub const DEFAULT_NAME: &str = "Alacritty Simplified";
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// Window title.
+    pub title: String,
+
+    /// Background opacity from 0.0 to 1.0.
+    pub opacity: Percentage,
+
+    /// Scale factor replacing the one reported by the windowing system.
+    scale_factor_override: Option<f64>,
+
+    /// Initial dimension state of the window.
+    pub startup_mode: StartupMode,
+
+    /// Spread out additional padding evenly.
+    pub dynamic_padding: bool,
+
+    /// Pixel padding.
+    padding: Delta<u8>,
+
+    /// Initial position.
+    pub position: Option<Delta<i32>>,
+
+    /// Initial dimensions.
+    pub dimensions: Dimensions,
+
+    /// Restore the window geometry from the last time it was closed.
+    pub remember_geometry: bool,
+
+    /// Enable secure keyboard entry while the window is focused.
+    pub secure_keyboard_entry: bool,
+
+    /// Controls which `Option` key should be treated as `Alt`.
+    option_as_alt: OptionAsAlt,
+}
+
+impl_replace_struct!(WindowConfig {
+    title,
+    opacity,
+    scale_factor_override,
+    startup_mode,
+    dynamic_padding,
+    padding,
+    position,
+    dimensions,
+    remember_geometry,
+    secure_keyboard_entry,
+    option_as_alt
+});
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: DEFAULT_NAME.into(),
+            opacity: Default::default(),
+            scale_factor_override: Default::default(),
+            startup_mode: Default::default(),
+            dynamic_padding: Default::default(),
+            padding: Default::default(),
+            position: Default::default(),
+            dimensions: Default::default(),
+            remember_geometry: Default::default(),
+            secure_keyboard_entry: Default::default(),
+            option_as_alt: Default::default(),
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Scale factor for the window, given the one reported by the windowing system.
+    #[inline]
+    pub fn scale_factor(&self, detected: f64) -> f64 {
+        self.scale_factor_override
+            .filter(|scale_factor| scale_factor.is_finite() && *scale_factor > 0.)
+            .unwrap_or(detected)
+    }
+
+    #[inline]
+    pub fn dimensions(&self) -> Option<Dimensions> {
+        let is_zero = self.dimensions.columns == 0 || self.dimensions.lines == 0;
+        if !is_zero && self.startup_mode == StartupMode::Windowed {
+            Some(self.dimensions)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn padding(&self, scale_factor: f32) -> (f32, f32) {
+        let padding_x = (f32::from(self.padding.x) * scale_factor).floor();
+        let padding_y = (f32::from(self.padding.y) * scale_factor).floor();
+        (padding_x, padding_y)
+    }
+
+    #[inline]
+    pub fn fullscreen(&self) -> Option<Fullscreen> {
+        if self.startup_mode == StartupMode::Fullscreen {
+            Some(Fullscreen::Borderless(None))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn maximized(&self) -> bool {
+        self.startup_mode == StartupMode::Maximized
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn option_as_alt(&self) -> WinitOptionAsAlt {
+        match self.option_as_alt {
+            OptionAsAlt::OnlyLeft => WinitOptionAsAlt::OnlyLeft,
+            OptionAsAlt::OnlyRight => WinitOptionAsAlt::OnlyRight,
+            OptionAsAlt::Both => WinitOptionAsAlt::Both,
+            OptionAsAlt::None => WinitOptionAsAlt::None,
+        }
+    }
+}
+
+/// Window dimensions in cells.
+#[derive(Deserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Dimensions {
+    /// Window width in character columns.
+    pub columns: usize,
+
+    /// Window height in character lines.
+    pub lines: usize,
+}
+
+impl_replace_struct!(Dimensions { columns, lines });
+
+#[derive(Deserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StartupMode {
+    #[default]
+    Windowed,
+    Maximized,
+    Fullscreen,
+    #[cfg(target_os = "macos")]
+    SimpleFullscreen,
+}
+
+impl_replace_value!(StartupMode);
+
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionAsAlt {
+    /// The left `Option` key is treated as `Alt`.
+    OnlyLeft,
+
+    /// The right `Option` key is treated as `Alt`.
+    OnlyRight,
+
+    /// Both `Option` keys are treated as `Alt`.
+    Both,
+
+    /// No special handling is applied for `Option` key.
+    #[default]
+    None,
+}
+
+impl_replace_value!(OptionAsAlt);
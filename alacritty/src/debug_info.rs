@@ -0,0 +1,98 @@
+//! Environment information for bug reports.
+
+use std::env;
+use std::error::Error;
+
+use crossfont::{Rasterize, Rasterizer};
+#[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+use glutin::platform::x11::X11GlConfigExt;
+use glutin::prelude::*;
+use raw_window_handle::HasRawDisplayHandle;
+
+use crate::cli::Options;
+use crate::config::{self, UiConfig};
+use crate::display::window::Window;
+use crate::renderer::text::GlyphCache;
+use crate::renderer::{self, GlInfo};
+use crate::{display, event};
+
+/// Print information about the environment Alacritty runs in.
+///
+/// This creates a hidden window to query the same details the display and renderer use at startup.
+pub fn print(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut config = config::load(options);
+    options.window_options.config_overrides().override_config(&mut config);
+
+    println!("Version: {}", env!("VERSION"));
+    println!("Platform: {} {}", env::consts::OS, env::consts::ARCH);
+
+    println!("Configuration files:");
+    for path in &config.config_paths {
+        println!("  {:?}", path.display());
+    }
+
+    println!("Configuration changes:");
+    let changes = toml::to_string(&config::load_changes(&config, options))?;
+    for line in changes.lines().filter(|line| !line.is_empty()) {
+        println!("  {line}");
+    }
+
+    let event_loop = event::create_event_loop()?;
+    let raw_display_handle = event_loop.raw_display_handle();
+
+    #[cfg(windows)]
+    let window = Window::new(&event_loop, &config)?;
+    #[cfg(windows)]
+    let raw_window_handle = Some(window.raw_window_handle());
+
+    #[cfg(not(windows))]
+    let raw_window_handle = None;
+
+    let gl_display =
+        renderer::platform::create_gl_display(raw_display_handle, raw_window_handle, false)?;
+    let gl_config = renderer::platform::pick_gl_config(&gl_display, raw_window_handle)?;
+
+    #[cfg(not(windows))]
+    let window = Window::new(
+        &event_loop,
+        &config,
+        #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+        gl_config.x11_visual(),
+    )?;
+
+    let gl_context =
+        renderer::platform::create_gl_context(&gl_display, &gl_config, raw_window_handle)?;
+    let surface = renderer::platform::create_gl_surface(
+        &gl_context,
+        window.inner_size(),
+        window.raw_window_handle(),
+    )?;
+    let context = gl_context.make_current(&surface)?;
+
+    let gl_info = GlInfo::new(&context)?;
+    println!("Renderer: {}", gl_info.renderer);
+    println!("OpenGL version: {}", gl_info.version);
+    println!("Shader version: {}", gl_info.shader_version);
+
+    println!("Scale factor: {} (detected {})", window.scale_factor, window.detected_scale_factor());
+
+    print_fonts(&config, &window)
+}
+
+/// Print the font configuration and the metrics it resolved to.
+fn print_fonts(config: &UiConfig, window: &Window) -> Result<(), Box<dyn Error>> {
+    let font_size = config.font.size().scale(window.scale_factor as f32);
+    let font = config.font.clone().with_size(font_size);
+    let glyph_cache = GlyphCache::new(Rasterizer::new()?, &font)?;
+
+    println!("Font size: {}pt", font_size.as_pt());
+
+    let desc = font.normal();
+    let style = desc.style.as_deref().unwrap_or("default style");
+    println!("Font normal: {:?} {style}", desc.family);
+
+    let (cell_width, cell_height) = display::compute_cell_size(&font, &glyph_cache.font_metrics());
+    println!("Cell size: {cell_width} x {cell_height}");
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! Capture of rendered frames for debugging.
+
+use std::error::Error;
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::mem;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use log::{error, info};
+use serde::Serialize;
+use winit::window::WindowId;
+
+use crate::display::SizeInfo;
+use crate::renderer::Renderer;
+
+/// Capture of a sequence of frames to PNG files.
+///
+/// Together with the frames, a JSON timeline of the events processed before each frame is
+/// written to the same directory.
+pub struct FrameCapture {
+    /// Directory the frames are written to.
+    directory: PathBuf,
+
+    /// Prefix for all written files.
+    prefix: String,
+
+    /// Number of frames which still need to be captured.
+    remaining: usize,
+
+    /// Events received since the last captured frame.
+    events: Vec<String>,
+
+    /// All captured frames.
+    timeline: Vec<CapturedFrame>,
+
+    /// Time at which the capture was started.
+    start: Instant,
+}
+
+/// Entry in the timeline of captured frames.
+#[derive(Serialize, Debug)]
+struct CapturedFrame {
+    /// Name of the PNG file.
+    file: String,
+
+    /// Seconds since the capture was started.
+    time: f64,
+
+    /// Events processed before the frame was drawn.
+    events: Vec<String>,
+}
+
+impl FrameCapture {
+    pub fn new(directory: PathBuf, count: usize, window_id: WindowId) -> Self {
+        Self {
+            directory,
+            prefix: format!("alacritty-{}", u64::from(window_id)),
+            remaining: count,
+            events: Default::default(),
+            timeline: Default::default(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Record an event for the next captured frame.
+    pub fn record_event<E: Debug>(&mut self, event: &E) {
+        self.events.push(format!("{event:?}"));
+    }
+
+    /// Capture the frame which was just drawn.
+    ///
+    /// Returns `false` once the capture has finished.
+    pub fn capture(&mut self, renderer: &Renderer, size_info: &SizeInfo) -> bool {
+        if let Err(err) = self.capture_frame(renderer, size_info) {
+            error!("Unable to capture frame to {:?}: {}", self.directory, err);
+            return false;
+        }
+
+        self.remaining = self.remaining.saturating_sub(1);
+        if self.remaining > 0 {
+            return true;
+        }
+
+        match self.write_timeline() {
+            Ok(()) => info!("Captured {} frames to {:?}", self.timeline.len(), self.directory),
+            Err(err) => error!("Unable to write frame timeline to {:?}: {}", self.directory, err),
+        }
+
+        false
+    }
+
+    fn capture_frame(
+        &mut self,
+        renderer: &Renderer,
+        size_info: &SizeInfo,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.timeline.is_empty() {
+            fs::create_dir_all(&self.directory)?;
+        }
+
+        let (width, height) = (size_info.width() as u32, size_info.height() as u32);
+        let pixels = renderer.read_pixels(width, height);
+
+        // OpenGL rows start at the bottom, while PNG rows start at the top.
+        let row_len = width as usize * 4;
+        let flipped: Vec<u8> = pixels.chunks_exact(row_len).rev().flatten().copied().collect();
+
+        let file = format!("{}-{:04}.png", self.prefix, self.timeline.len());
+        let writer = BufWriter::new(File::create(self.directory.join(&file))?);
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&flipped)?;
+
+        self.timeline.push(CapturedFrame {
+            file,
+            time: self.start.elapsed().as_secs_f64(),
+            events: mem::take(&mut self.events),
+        });
+
+        Ok(())
+    }
+
+    fn write_timeline(&self) -> Result<(), Box<dyn Error>> {
+        let path = self.directory.join(format!("{}-timeline.json", self.prefix));
+        fs::write(path, serde_json::to_string_pretty(&self.timeline)?)?;
+        Ok(())
+    }
+}
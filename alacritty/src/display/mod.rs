@@ -1,10 +1,12 @@
 //! The display subsystem including window management, font rasterization, and
 //! GPU drawing.
 
+use std::cmp;
 use std::fmt::{self, Formatter};
 use std::mem::{self, ManuallyDrop};
 use std::num::NonZeroU32;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 
 use glutin::context::{NotCurrentContext, PossiblyCurrentContext};
@@ -13,19 +15,39 @@ use glutin::surface::{Surface, SwapInterval, WindowSurface};
 
 use log::{debug, info};
 use raw_window_handle::RawWindowHandle;
-use serde::{Deserialize, Serialize};
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serialize};
 use winit::dpi::PhysicalSize;
 
-use crossfont::{self};
+use crossfont::{self, Rasterize, Rasterizer};
 
+use crate::config::font::Font;
+use crate::config::serde_utils::impl_replace_value;
+use crate::config::window::Dimensions;
+use crate::config::UiConfig;
+#[cfg(unix)]
+use crate::display::capture::FrameCapture;
+use crate::display::meter::Meter;
 use crate::display::window::Window;
 use crate::event::{Event, EventType};
+use crate::message_bar::{MessageBuffer, MessageType};
 use crate::renderer::rects::RenderRect;
+use crate::renderer::text::GlyphCache;
 use crate::renderer::{self, Renderer};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 
+#[cfg(unix)]
+pub mod capture;
 pub mod window;
 
+mod meter;
+
+/// Minimum number of columns.
+const MIN_COLUMNS: usize = 2;
+
+/// Minimum number of visible lines.
+const MIN_SCREEN_LINES: usize = 1;
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
 pub struct Rgb {
     pub r: u8,
@@ -45,6 +67,64 @@ impl Rgb {
     }
 }
 
+impl_replace_value!(Rgb);
+
+impl FromStr for Rgb {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Rgb, ()> {
+        let chars = if s.starts_with("0x") && s.len() == 8 {
+            &s[2..]
+        } else if s.starts_with('#') && s.len() == 7 {
+            &s[1..]
+        } else {
+            return Err(());
+        };
+
+        match u32::from_str_radix(chars, 16) {
+            Ok(mut color) => {
+                let b = (color & 0xff) as u8;
+                color >>= 8;
+                let g = (color & 0xff) as u8;
+                color >>= 8;
+                let r = color as u8;
+                Ok(Rgb::new(r, g, b))
+            },
+            Err(_) => Err(()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RgbVisitor;
+
+        impl<'a> Visitor<'a> for RgbVisitor {
+            type Value = Rgb;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("hex color like #ff00ff")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Rgb, E>
+            where
+                E: serde::de::Error,
+            {
+                Rgb::from_str(value).map_err(|_| {
+                    E::custom(format!(
+                        "failed to parse rgb color {value}; expected hex color like #ff00ff"
+                    ))
+                })
+            }
+        }
+
+        deserializer.deserialize_str(RgbVisitor)
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     /// Error with window management.
@@ -114,11 +194,38 @@ pub struct SizeInfo<T = f32> {
 
     /// Terminal window height.
     height: T,
+
+    /// Width of individual cell.
+    cell_width: T,
+
+    /// Height of individual cell.
+    cell_height: T,
+
+    /// Horizontal window padding.
+    padding_x: T,
+
+    /// Vertical window padding.
+    padding_y: T,
+
+    /// Number of lines in the viewport.
+    screen_lines: usize,
+
+    /// Number of columns in the viewport.
+    columns: usize,
 }
 
 impl From<SizeInfo<f32>> for SizeInfo<u32> {
     fn from(size_info: SizeInfo<f32>) -> Self {
-        Self { width: size_info.width as u32, height: size_info.height as u32 }
+        Self {
+            width: size_info.width as u32,
+            height: size_info.height as u32,
+            cell_width: size_info.cell_width as u32,
+            cell_height: size_info.cell_height as u32,
+            padding_x: size_info.padding_x as u32,
+            padding_y: size_info.padding_y as u32,
+            screen_lines: size_info.screen_lines,
+            columns: size_info.columns,
+        }
     }
 }
 
@@ -132,12 +239,87 @@ impl<T: Clone + Copy> SizeInfo<T> {
     pub fn height(&self) -> T {
         self.height
     }
+
+    #[inline]
+    pub fn cell_width(&self) -> T {
+        self.cell_width
+    }
+
+    #[inline]
+    pub fn cell_height(&self) -> T {
+        self.cell_height
+    }
+
+    #[inline]
+    pub fn padding_x(&self) -> T {
+        self.padding_x
+    }
+
+    #[inline]
+    pub fn padding_y(&self) -> T {
+        self.padding_y
+    }
+
+    #[inline]
+    pub fn screen_lines(&self) -> usize {
+        self.screen_lines
+    }
+
+    #[inline]
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
 }
 
 impl SizeInfo<f32> {
     #[allow(clippy::too_many_arguments)]
-    pub fn new(width: f32, height: f32) -> SizeInfo {
-        SizeInfo { width, height }
+    pub fn new(
+        width: f32,
+        height: f32,
+        cell_width: f32,
+        cell_height: f32,
+        mut padding_x: f32,
+        mut padding_y: f32,
+        dynamic_padding: bool,
+    ) -> SizeInfo {
+        if dynamic_padding {
+            padding_x = Self::dynamic_padding(padding_x.floor(), width, cell_width);
+            padding_y = Self::dynamic_padding(padding_y.floor(), height, cell_height);
+        }
+
+        let lines = (height - 2. * padding_y) / cell_height;
+        let screen_lines = cmp::max(lines as usize, MIN_SCREEN_LINES);
+
+        let columns = (width - 2. * padding_x) / cell_width;
+        let columns = cmp::max(columns as usize, MIN_COLUMNS);
+
+        SizeInfo {
+            width,
+            height,
+            cell_width,
+            cell_height,
+            padding_x: padding_x.floor(),
+            padding_y: padding_y.floor(),
+            screen_lines,
+            columns,
+        }
+    }
+
+    /// Number of lines fitting into the window, including reserved lines.
+    pub fn total_lines(&self) -> usize {
+        let lines = (self.height - 2. * self.padding_y) / self.cell_height;
+        cmp::max(lines as usize, MIN_SCREEN_LINES)
+    }
+
+    /// Reserve lines at the bottom of the viewport, like for the message bar.
+    pub fn reserve_lines(&mut self, count: usize) {
+        self.screen_lines = cmp::max(self.screen_lines.saturating_sub(count), MIN_SCREEN_LINES);
+    }
+
+    /// Calculate padding to spread it evenly around the terminal content.
+    #[inline]
+    fn dynamic_padding(padding: f32, dimension: f32, cell_dimension: f32) -> f32 {
+        padding + ((dimension - 2. * padding) % cell_dimension) / 2.
     }
 }
 
@@ -147,6 +329,7 @@ pub struct DisplayUpdate {
 
     dimensions: Option<PhysicalSize<u32>>,
     cursor_dirty: bool,
+    font: Option<Font>,
 }
 
 impl DisplayUpdate {
@@ -154,6 +337,15 @@ impl DisplayUpdate {
         self.dimensions
     }
 
+    pub fn font(&self) -> Option<&Font> {
+        self.font.as_ref()
+    }
+
+    pub fn set_font(&mut self, font: Font) {
+        self.font = Some(font);
+        self.dirty = true;
+    }
+
     pub fn set_dimensions(&mut self, dimensions: PhysicalSize<u32>) {
         self.dimensions = Some(dimensions);
         self.dirty = true;
@@ -168,9 +360,6 @@ pub struct Display {
 
     pub raw_window_handle: RawWindowHandle,
 
-    /// UI cursor visibility for blinking.
-    pub cursor_hidden: bool,
-
     /// Unprocessed display updates.
     pub pending_update: DisplayUpdate,
 
@@ -180,17 +369,28 @@ pub struct Display {
     /// The state of the timer for frame scheduling.
     pub frame_timer: FrameTimer,
 
+    /// Frames which should be captured for debugging.
+    #[cfg(unix)]
+    pub frame_capture: Option<FrameCapture>,
+
     renderer: ManuallyDrop<Renderer>,
 
+    glyph_cache: GlyphCache,
+    meter: Meter,
+
     surface: ManuallyDrop<Surface<WindowSurface>>,
 
     context: ManuallyDrop<Replaceable<PossiblyCurrentContext>>,
+
+    /// Whether the glyph cache still has to be filled with common glyphs.
+    common_glyphs_pending: bool,
 }
 
 impl Display {
     pub fn new(
         window: Window,
         gl_context: NotCurrentContext,
+        config: &UiConfig,
         _tabbed: bool,
     ) -> Result<Display, Error> {
         let raw_window_handle = window.raw_window_handle();
@@ -202,16 +402,18 @@ impl Display {
             window.raw_window_handle(),
         )?;
 
+        let gl_start = Instant::now();
         let context = gl_context.make_current(&surface)?;
 
         let renderer = Renderer::new(&context)?;
+        debug!("OpenGL initialized in {:?}", gl_start.elapsed());
 
-        let viewport_size = window.inner_size();
-
-        // Create new size with at least one column and row.
-        let size_info = SizeInfo::new(viewport_size.width as f32, viewport_size.height as f32);
-
-        renderer.clear(Rgb::new(24, 24, 24), 1.0);
+        // Clear screen.
+        //
+        // This happens before loading the fonts, so the window shows up with the configured
+        // background even when rasterization is slow due to a cold font cache.
+        let background_color = config.colors.primary.background;
+        renderer.clear(background_color, config.window_opacity());
 
         // On Wayland we can safely ignore this call, since the window isn't visible until you
         // actually draw something into it and commit those changes.
@@ -221,7 +423,54 @@ impl Display {
             renderer.finish();
         }
 
-        window.set_visible(true);
+        // Show the window right away, unless it still has to be resized to the configured
+        // dimensions once the cell size is known.
+        let dimensions = config.window.dimensions();
+        if dimensions.is_none() {
+            window.set_visible(true);
+        }
+
+        let scale_factor = window.scale_factor as f32;
+        let rasterizer = Rasterizer::new()?;
+
+        let font_size = config.font.size().scale(scale_factor);
+        debug!("Loading \"{}\" font", &config.font.normal().family);
+        let font_start = Instant::now();
+        let font = config.font.clone().with_size(font_size);
+        let glyph_cache = GlyphCache::new(rasterizer, &font)?;
+        debug!("Font loaded in {:?}", font_start.elapsed());
+
+        let metrics = glyph_cache.font_metrics();
+        let (cell_width, cell_height) = compute_cell_size(&font, &metrics);
+
+        // Resize the window to account for the user configured size.
+        if let Some(dimensions) = dimensions {
+            let size = window_size(config, dimensions, cell_width, cell_height, scale_factor);
+            window.request_inner_size(size);
+            window.set_visible(true);
+        }
+
+        let padding = config.window.padding(window.scale_factor as f32);
+        let viewport_size = window.inner_size();
+
+        // Create new size with at least one column and row.
+        let size_info = SizeInfo::new(
+            viewport_size.width as f32,
+            viewport_size.height as f32,
+            cell_width,
+            cell_height,
+            padding.0,
+            padding.1,
+            config.window.dynamic_padding,
+        );
+
+        info!("Cell size: {} x {}", cell_width, cell_height);
+        info!("Window scale factor: {}", window.scale_factor);
+        info!("Width: {}, Height: {}", size_info.width(), size_info.height());
+        info!("Columns: {}, Lines: {}", size_info.columns(), size_info.screen_lines());
+
+        // Update OpenGL projection.
+        renderer.resize(&size_info);
 
         // Disable vsync.
         if let Err(err) = surface.set_swap_interval(&context, SwapInterval::DontWait) {
@@ -233,12 +482,16 @@ impl Display {
             renderer: ManuallyDrop::new(renderer),
             surface: ManuallyDrop::new(surface),
             frame_timer: FrameTimer::new(),
+            #[cfg(unix)]
+            frame_capture: None,
+            meter: Meter::default(),
             raw_window_handle,
+            glyph_cache,
             size_info,
             window,
             pending_renderer_update: Default::default(),
             pending_update: Default::default(),
-            cursor_hidden: Default::default(),
+            common_glyphs_pending: true,
         })
     }
 
@@ -254,11 +507,7 @@ impl Display {
     }
 
     fn swap_buffers(&self) {
-        #[allow(clippy::single_match)]
-        let res = match (self.surface.deref(), &self.context.get()) {
-            (surface, context) => surface.swap_buffers(context),
-        };
-        if let Err(err) = res {
+        if let Err(err) = self.surface.swap_buffers(self.context.get()) {
             debug!("error calling swap_buffers: {}", err);
         }
     }
@@ -267,16 +516,46 @@ impl Display {
     // performed in [`Self::process_renderer_update`] right before drawing.
     //
     /// Process update events.
-    pub fn handle_update(&mut self) {
+    pub fn handle_update(&mut self, message_buffer: &MessageBuffer, config: &UiConfig) {
         let pending_update = mem::take(&mut self.pending_update);
 
+        let (mut cell_width, mut cell_height) =
+            (self.size_info.cell_width(), self.size_info.cell_height());
+
+        // Update font size and cell dimensions.
+        if let Some(font) = pending_update.font() {
+            let cell_dimensions = self.update_font_size(font);
+            cell_width = cell_dimensions.0;
+            cell_height = cell_dimensions.1;
+
+            info!("Cell size: {} x {}", cell_width, cell_height);
+
+            // Glyphs must be rasterized again at the new size.
+            let renderer_update = self.pending_renderer_update.get_or_insert(Default::default());
+            renderer_update.clear_font_cache = true;
+        }
+
         let (mut width, mut height) = (self.size_info.width(), self.size_info.height());
         if let Some(dimensions) = pending_update.dimensions() {
             width = dimensions.width as f32;
             height = dimensions.height as f32;
         }
 
-        let new_size = SizeInfo::new(width, height);
+        let padding = config.window.padding(self.window.scale_factor as f32);
+
+        let mut new_size = SizeInfo::new(
+            width,
+            height,
+            cell_width,
+            cell_height,
+            padding.0,
+            padding.1,
+            config.window.dynamic_padding,
+        );
+
+        // Update number of lines in the viewport.
+        let message_bar_lines = message_buffer.message().map_or(0, |m| m.text(&new_size).len());
+        new_size.reserve_lines(message_bar_lines);
 
         // Check if dimensions have changed.
         if new_size != self.size_info {
@@ -307,6 +586,30 @@ impl Display {
 
         // Ensure we're modifying the correct OpenGL context.
         self.make_current();
+
+        if renderer_update.clear_font_cache {
+            self.reset_glyph_cache();
+        }
+
+        self.renderer.resize(&self.size_info);
+    }
+
+    /// Update font size and cell dimensions.
+    ///
+    /// This will return a tuple of the cell width and height.
+    fn update_font_size(&mut self, font: &Font) -> (f32, f32) {
+        let _ = self.glyph_cache.update_font_size(font);
+
+        // Compute new cell sizes.
+        compute_cell_size(font, &self.glyph_cache.font_metrics())
+    }
+
+    /// Reset glyph cache.
+    fn reset_glyph_cache(&mut self) {
+        let cache = &mut self.glyph_cache;
+        self.renderer.with_loader(|mut api| {
+            cache.reset_glyph_cache(&mut api);
+        });
     }
 
     /// Draw the screen.
@@ -314,22 +617,83 @@ impl Display {
     /// A reference to Term whose state is being drawn must be provided.
     ///
     /// This call may block if vsync is enabled.
-    pub fn draw(&mut self, scheduler: &mut Scheduler) {
+    pub fn draw(
+        &mut self,
+        scheduler: &mut Scheduler,
+        message_buffer: &MessageBuffer,
+        config: &UiConfig,
+    ) {
         let size_info = self.size_info;
 
         // Make sure this window's OpenGL context is active.
         self.make_current();
 
-        self.renderer.clear(Rgb::new(24, 24, 24), 1.0);
+        let background_color = config.colors.primary.background;
+        self.renderer.clear(background_color, config.window_opacity());
+
+        {
+            let _sampler = self.meter.sampler();
+
+            // Ensure macOS hasn't reset our viewport.
+            #[cfg(target_os = "macos")]
+            self.renderer.set_viewport(&size_info);
+
+            let mut rects = vec![
+                RenderRect::new(10., 10., 100., 50., Rgb::new(255, 0, 0), 1.),
+                RenderRect::new(500., 200., 100., 50., Rgb::new(255, 255, 0), 1.),
+            ];
+
+            if let Some(message) = message_buffer.message() {
+                let text = message.text(&size_info);
+
+                // Create a new rectangle for the background.
+                let start_line = size_info.screen_lines();
+                let y = size_info.cell_height().mul_add(start_line as f32, size_info.padding_y());
+
+                let bg = match message.ty() {
+                    MessageType::Error => config.colors.normal.red,
+                    MessageType::Warning => config.colors.normal.yellow,
+                };
+
+                let message_bar_rect =
+                    RenderRect::new(0., y, size_info.width(), size_info.height() - y, bg, 1.);
+
+                // Push message_bar in the end, so it'll be above all other content.
+                rects.push(message_bar_rect);
+
+                // Draw rectangles.
+                self.renderer.draw_rects(&size_info, rects);
+
+                // Relay messages to the user.
+                let glyph_cache = &mut self.glyph_cache;
+                let fg = config.colors.primary.background;
+                for (i, message_text) in text.iter().enumerate() {
+                    self.renderer.draw_string(
+                        start_line + i,
+                        0,
+                        fg,
+                        bg,
+                        1.,
+                        message_text.chars(),
+                        &size_info,
+                        glyph_cache,
+                    );
+                }
+            } else {
+                // Draw rectangles.
+                self.renderer.draw_rects(&size_info, rects);
+            }
+        }
 
-        // Ensure macOS hasn't reset our viewport.
-        #[cfg(target_os = "macos")]
-        self.renderer.set_viewport(&size_info);
+        self.draw_render_timer(config);
 
-        let mut rects: Vec<RenderRect> = Vec::new();
-        rects.push(RenderRect::new(10., 10., 100., 50., Rgb::new(255, 0, 0), 1.));
-        rects.push(RenderRect::new(500., 200., 100., 50., Rgb::new(255, 255, 0), 1.));
-        self.renderer.draw_rects(&size_info, rects);
+        // Capture the frame before it is presented.
+        #[cfg(unix)]
+        if let Some(frame_capture) = &mut self.frame_capture {
+            if !frame_capture.capture(&self.renderer, &size_info) {
+                self.frame_capture = None;
+            }
+        }
 
         // Notify winit that we're about to present.
         self.window.pre_present_notify();
@@ -344,6 +708,16 @@ impl Display {
             self.renderer.finish();
         }
 
+        // Load font common glyphs to accelerate rendering, deferred to avoid delaying the first
+        // frame.
+        if mem::take(&mut self.common_glyphs_pending) {
+            debug!("Filling glyph cache with common glyphs");
+            let glyph_cache = &mut self.glyph_cache;
+            self.renderer.with_loader(|mut api| {
+                glyph_cache.load_common_glyphs(&mut api);
+            });
+        }
+
         // XXX: Request the new frame after swapping buffers, so the
         // time to finish OpenGL operations is accounted for in the timeout.
         if !matches!(self.raw_window_handle, RawWindowHandle::Wayland(_)) {
@@ -351,6 +725,32 @@ impl Display {
         }
     }
 
+    /// Draw render timer.
+    #[inline(never)]
+    fn draw_render_timer(&mut self, config: &UiConfig) {
+        if !config.debug.render_timer {
+            return;
+        }
+
+        let timing = format!("{:.3} usec", self.meter.average());
+        let line = self.size_info.screen_lines().saturating_sub(2);
+        let fg = config.colors.primary.background;
+        let bg = config.colors.primary.foreground;
+        let bg_alpha = config.cell_background_opacity();
+
+        let glyph_cache = &mut self.glyph_cache;
+        self.renderer.draw_string(
+            line,
+            0,
+            fg,
+            bg,
+            bg_alpha,
+            timing.chars(),
+            &self.size_info,
+            glyph_cache,
+        );
+    }
+
     /// Request a new frame for a window on Wayland.
     fn request_frame(&mut self, scheduler: &mut Scheduler) {
         // Mark that we've used a frame.
@@ -399,6 +799,41 @@ impl Drop for Display {
 pub struct RendererUpdate {
     /// Should resize the window.
     resize: bool,
+
+    /// Clear font caches.
+    clear_font_cache: bool,
+}
+
+/// Calculate the cell dimensions based on font metrics.
+///
+/// This will return a tuple of the cell width and height.
+#[inline]
+pub fn compute_cell_size(font: &Font, metrics: &crossfont::Metrics) -> (f32, f32) {
+    let offset_x = f64::from(font.offset.x);
+    let offset_y = f64::from(font.offset.y);
+    (
+        (metrics.average_advance + offset_x).floor().max(1.) as f32,
+        (metrics.line_height + offset_y).floor().max(1.) as f32,
+    )
+}
+
+/// Calculate the size of the window given padding, terminal dimensions and cell size.
+fn window_size(
+    config: &UiConfig,
+    dimensions: Dimensions,
+    cell_width: f32,
+    cell_height: f32,
+    scale_factor: f32,
+) -> PhysicalSize<u32> {
+    let padding = config.window.padding(scale_factor);
+
+    let grid_width = cell_width * dimensions.columns.max(MIN_COLUMNS) as f32;
+    let grid_height = cell_height * dimensions.lines.max(MIN_SCREEN_LINES) as f32;
+
+    let width = (padding.0).mul_add(2., grid_width).floor();
+    let height = (padding.1).mul_add(2., grid_height).floor();
+
+    PhysicalSize::new(width as u32, height as u32)
 }
 
 /// Struct for safe in-place replacement.
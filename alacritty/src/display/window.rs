@@ -10,30 +10,37 @@ use winit::platform::wayland::WindowBuilderExtWayland;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use {
     std::io::Cursor,
-    winit::platform::x11::{WindowBuilderExtX11, EventLoopWindowTargetExtX11},
+    winit::platform::x11::WindowBuilderExtX11,
     glutin::platform::x11::X11VisualInfo,
     winit::window::Icon,
     png::Decoder,
 };
 
 use std::fmt::{self, Display, Formatter};
+#[cfg(target_os = "macos")]
+use std::mem;
 
 #[cfg(target_os = "macos")]
 use {
     cocoa::appkit::NSColorSpace,
     cocoa::base::{id, nil},
     objc::{msg_send, sel, sel_impl},
+    winit::platform::macos::{OptionAsAlt, WindowBuilderExtMacOS, WindowExtMacOS},
 };
 
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event_loop::EventLoopWindowTarget;
 use winit::monitor::MonitorHandle;
 #[cfg(windows)]
 use winit::platform::windows::IconExtWindows;
-use winit::window::{
-    CursorIcon, Theme as WinitTheme, Window as WinitWindow, WindowBuilder, WindowId,
-};
+use winit::window::{Theme as WinitTheme, Window as WinitWindow, WindowBuilder, WindowId};
+
+#[cfg(target_os = "macos")]
+use crate::config::window::StartupMode;
+use crate::config::UiConfig;
+#[cfg(target_os = "macos")]
+use crate::macos::secure_input;
 
 /// Window icon for `_NET_WM_ICON` property.
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
@@ -99,6 +106,14 @@ pub struct Window {
     /// Flag indicating whether redraw was requested.
     pub requested_redraw: bool,
 
+    /// Title requested by the configuration.
+    #[cfg(target_os = "macos")]
+    title: String,
+
+    /// Whether secure keyboard entry is enabled for this window.
+    #[cfg(target_os = "macos")]
+    secure_input: bool,
+
     window: WinitWindow,
 }
 
@@ -108,6 +123,7 @@ impl Window {
     /// This creates a window and fully initializes a window.
     pub fn new<E>(
         event_loop: &EventLoopWindowTarget<E>,
+        config: &UiConfig,
         #[rustfmt::skip]
         #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
         x11_visual: Option<X11VisualInfo>,
@@ -127,23 +143,51 @@ impl Window {
             startup_notify::reset_activation_token_env();
         }
 
+        #[cfg(target_os = "macos")]
+        {
+            window_builder = window_builder.with_option_as_alt(config.window.option_as_alt());
+        }
+
+        if let Some(position) = config.window.position {
+            window_builder =
+                window_builder.with_position(PhysicalPosition::new(position.x, position.y));
+        }
+
+        // Follow the system theme when there are color schemes to switch between.
+        let theme = if config.colors.theme.is_empty() { Some(WinitTheme::Light) } else { None };
+
         let window = window_builder
-            .with_title("Alacritty Simplified")
-            .with_theme(Some(WinitTheme::Light))
+            .with_title(&config.window.title)
+            .with_theme(theme)
             .with_visible(false)
-            .with_transparent(true)
+            .with_transparent(config.window_opacity() < 1.)
             .with_blur(false)
-            .with_maximized(false)
-            .with_fullscreen(None)
+            .with_maximized(config.window.maximized())
+            .with_fullscreen(config.window.fullscreen())
             .build(event_loop)?;
 
         #[cfg(target_os = "macos")]
         use_srgb_color_space(&window);
 
-        let scale_factor = window.scale_factor();
-        println!("Window scale factor: {}", scale_factor);
+        // Enable simple fullscreen once the window has been created, since the builder doesn't
+        // support it.
+        #[cfg(target_os = "macos")]
+        if config.window.startup_mode == StartupMode::SimpleFullscreen {
+            window.set_simple_fullscreen(true);
+        }
 
-        Ok(Self { requested_redraw: false, has_frame: true, scale_factor, window })
+        let scale_factor = config.window.scale_factor(window.scale_factor());
+
+        Ok(Self {
+            requested_redraw: false,
+            has_frame: true,
+            scale_factor,
+            window,
+            #[cfg(target_os = "macos")]
+            title: config.window.title.clone(),
+            #[cfg(target_os = "macos")]
+            secure_input: false,
+        })
     }
 
     #[inline]
@@ -151,16 +195,100 @@ impl Window {
         self.window.raw_window_handle()
     }
 
+    /// Scale factor reported by the windowing system, ignoring any user override.
+    #[inline]
+    pub fn detected_scale_factor(&self) -> f64 {
+        self.window.scale_factor()
+    }
+
     #[inline]
     pub fn inner_size(&self) -> PhysicalSize<u32> {
         self.window.inner_size()
     }
 
+    #[inline]
+    pub fn outer_position(&self) -> Option<PhysicalPosition<i32>> {
+        self.window.outer_position().ok()
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        self.window.is_maximized()
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.window.fullscreen().is_some()
+    }
+
+    #[inline]
+    pub fn request_inner_size(&self, size: PhysicalSize<u32>) {
+        let _ = self.window.request_inner_size(size);
+    }
+
+    /// System theme of the window, if it is known.
+    #[inline]
+    pub fn theme(&self) -> Option<WinitTheme> {
+        self.window.theme()
+    }
+
     #[inline]
     pub fn set_visible(&self, visibility: bool) {
         self.window.set_visible(visibility);
     }
 
+    /// Set the window title.
+    #[inline]
+    #[cfg(not(target_os = "macos"))]
+    pub fn set_title(&self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    /// Set the window title.
+    #[cfg(target_os = "macos")]
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.into();
+
+        // Indicate active secure keyboard entry in the title.
+        if self.secure_input {
+            self.window.set_title(&format!("\u{1f512} {title}"));
+        } else {
+            self.window.set_title(title);
+        }
+    }
+
+    /// Update secure keyboard entry, enabling it only while the window is focused.
+    #[cfg(target_os = "macos")]
+    pub fn update_secure_input(&mut self, requested: bool, focused: bool) {
+        let enabled = requested && focused;
+        if enabled == self.secure_input {
+            return;
+        }
+
+        secure_input::set_enabled(enabled);
+        self.secure_input = enabled;
+
+        let title = mem::take(&mut self.title);
+        self.set_title(&title);
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn set_option_as_alt(&self, option_as_alt: OptionAsAlt) {
+        self.window.set_option_as_alt(option_as_alt);
+    }
+
+    /// Check whether the window has keyboard focus.
+    #[cfg(target_os = "macos")]
+    #[inline]
+    pub fn has_focus(&self) -> bool {
+        self.window.has_focus()
+    }
+
+    #[inline]
+    pub fn set_transparent(&self, transparent: bool) {
+        self.window.set_transparent(transparent);
+    }
+
     #[inline]
     pub fn request_redraw(&mut self) {
         if !self.requested_redraw {
@@ -228,6 +356,20 @@ impl Window {
     pub fn current_monitor(&self) -> Option<MonitorHandle> {
         self.window.current_monitor()
     }
+
+    pub fn available_monitors(&self) -> impl Iterator<Item = MonitorHandle> {
+        self.window.available_monitors()
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for Window {
+    fn drop(&mut self) {
+        // Balance secure keyboard entry, since it's enabled system-wide.
+        if self.secure_input {
+            secure_input::set_enabled(false);
+        }
+    }
 }
 
 #[cfg(target_os = "macos")]
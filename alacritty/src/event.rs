@@ -3,18 +3,33 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Debug;
+#[cfg(not(any(target_os = "macos", windows)))]
+use std::fmt::{self, Display as FmtDisplay, Formatter};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Instant;
 
 use ahash::RandomState;
-use glutin::display::{Display as GlutinDisplay, GetGlDisplay};
-use log::info;
+use glutin::config::{Config as GlutinConfig, GetGlConfig};
+use log::{error, info};
+#[cfg(not(any(target_os = "macos", windows)))]
+use winit::error::EventLoopError;
 use winit::event::{Event as WinitEvent, StartCause, WindowEvent};
 use winit::event_loop::{
-    ControlFlow, DeviceEvents, EventLoop, EventLoopProxy, EventLoopWindowTarget,
+    ControlFlow, DeviceEvents, EventLoop, EventLoopBuilder, EventLoopProxy, EventLoopWindowTarget,
 };
 use winit::window::WindowId;
 
+#[cfg(unix)]
+use crate::cli::{CaptureOptions, IpcConfig, ParsedOptions};
+use crate::cli::{Options, WindowOptions};
+use crate::config::{self, UiConfig};
 use crate::display::window::Window;
 use crate::display::Display;
+#[cfg(unix)]
+use crate::ipc;
+use crate::logging;
+use crate::message_bar::{Message, MessageBuffer};
 use crate::scheduler::Scheduler;
 use crate::window_context::WindowContext;
 
@@ -57,15 +72,24 @@ impl From<Event> for WinitEvent<Event> {
 /// Alacritty events.
 #[derive(Debug, Clone)]
 pub enum EventType {
-    SearchNext,
+    ConfigReload(PathBuf),
+    #[cfg(unix)]
+    IpcConfig(IpcConfig),
+    #[cfg(unix)]
+    CreateWindow(WindowOptions),
+    #[cfg(unix)]
+    CaptureFrames(CaptureOptions),
+    Message(Message),
     Frame,
+
+    /// Window was closed and its context has been dropped.
+    WindowDestroyed,
 }
 
 pub struct ActionContext<'a> {
     pub display: &'a mut Display,
-    pub event_loop: &'a EventLoopWindowTarget<Event>,
-    pub event_proxy: &'a EventLoopProxy<Event>,
-    pub scheduler: &'a mut Scheduler,
+    pub message_buffer: &'a mut MessageBuffer,
+    pub config: &'a UiConfig,
     pub dirty: &'a mut bool,
     pub occluded: &'a mut bool,
 }
@@ -81,7 +105,17 @@ impl InputProcessor<ActionContext<'_>> {
     /// Handle events from winit.
     pub fn handle_event(&mut self, event: WinitEvent<Event>) {
         match event {
-            WinitEvent::UserEvent(Event { payload: _, .. }) => (),
+            WinitEvent::UserEvent(Event { payload, .. }) => match payload {
+                EventType::Message(message) => {
+                    self.ctx.message_buffer.push(message);
+                    self.ctx.display.pending_update.dirty = true;
+                },
+                EventType::ConfigReload(_) | EventType::Frame | EventType::WindowDestroyed => (),
+                #[cfg(unix)]
+                EventType::IpcConfig(_)
+                | EventType::CreateWindow(_)
+                | EventType::CaptureFrames(_) => (),
+            },
             WinitEvent::WindowEvent { event, .. } => {
                 match event {
                     WindowEvent::Resized(size) => {
@@ -94,7 +128,30 @@ impl InputProcessor<ActionContext<'_>> {
 
                         self.ctx.display.pending_update.set_dimensions(size);
                     },
-                    WindowEvent::ScaleFactorChanged { scale_factor: _, .. } => {},
+                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        let scale_factor = self.ctx.config.window.scale_factor(scale_factor);
+                        self.ctx.window().scale_factor = scale_factor;
+
+                        // Rescale font size for the new factor.
+                        let font = self.ctx.config.font.clone();
+                        let font_size = font.size().scale(scale_factor as f32);
+                        self.ctx.display.pending_update.set_font(font.with_size(font_size));
+                    },
+                    #[cfg(target_os = "macos")]
+                    WindowEvent::Focused(focused) => {
+                        let secure_keyboard_entry = self.ctx.config.window.secure_keyboard_entry;
+                        self.ctx.window().update_secure_input(secure_keyboard_entry, focused);
+                    },
+                    #[cfg(not(target_os = "macos"))]
+                    WindowEvent::Focused(_) => (),
+                    WindowEvent::Occluded(occluded) => {
+                        *self.ctx.occluded = occluded;
+
+                        // Redraw the window once it becomes visible again.
+                        if !occluded {
+                            *self.ctx.dirty = true;
+                        }
+                    },
                     WindowEvent::ActivationTokenDone { .. }
                     | WindowEvent::HoveredFileCancelled
                     | WindowEvent::Destroyed
@@ -103,12 +160,10 @@ impl InputProcessor<ActionContext<'_>> {
                     | WindowEvent::RedrawRequested
                     | WindowEvent::CloseRequested
                     | WindowEvent::Moved(_)
-                    | WindowEvent::Focused(_)
-                    | WindowEvent::Occluded(_)
                     | WindowEvent::DroppedFile(_) => (),
                 }
             },
-            WinitEvent::Suspended { .. }
+            WinitEvent::Suspended
             | WinitEvent::NewEvents { .. }
             | WinitEvent::LoopExiting
             | WinitEvent::Resumed
@@ -124,15 +179,49 @@ impl InputProcessor<ActionContext<'_>> {
 /// triggered.
 pub struct Processor {
     windows: HashMap<WindowId, WindowContext, RandomState>,
-    gl_display: Option<GlutinDisplay>,
+    gl_config: Option<GlutinConfig>,
+    config: Rc<UiConfig>,
+    initial_window_options: Option<WindowOptions>,
+
+    /// Time at which startup began, until the first frame has been drawn.
+    startup_start: Option<Instant>,
+
+    /// IPC socket path override.
+    #[cfg(unix)]
+    socket: Option<PathBuf>,
+
+    /// Path of the IPC socket, once it has been created.
+    #[cfg(unix)]
+    pub socket_path: Option<PathBuf>,
 }
 
 impl Processor {
     /// Create a new event processor.
     ///
     /// Takes a writer which is expected to be hooked up to the write end of a PTY.
-    pub fn new(_event_loop: &EventLoop<Event>) -> Processor {
-        Processor { gl_display: None, windows: Default::default() }
+    pub fn new(
+        config: UiConfig,
+        cli_options: &Options,
+        _event_loop: &EventLoop<Event>,
+    ) -> Processor {
+        Processor {
+            initial_window_options: Some(cli_options.window_options.clone()),
+            gl_config: None,
+            config: Rc::new(config),
+            windows: Default::default(),
+            startup_start: Some(Instant::now()),
+            #[cfg(unix)]
+            socket: cli_options.socket.clone(),
+            #[cfg(unix)]
+            socket_path: None,
+        }
+    }
+
+    /// Report the startup time, once the first frame has been presented.
+    fn finish_startup(&mut self) {
+        if let Some(startup_start) = self.startup_start.take() {
+            info!("First frame drawn after {:?}", startup_start.elapsed());
+        }
     }
 
     /// Create initial window and load GL platform.
@@ -142,15 +231,54 @@ impl Processor {
     pub fn create_initial_window(
         &mut self,
         event_loop: &EventLoopWindowTarget<Event>,
+        options: WindowOptions,
     ) -> Result<(), Box<dyn Error>> {
-        let window_context = WindowContext::initial(event_loop)?;
+        let mut window_context = WindowContext::initial(event_loop, self.config.clone(), options)?;
+
+        // Use the colors for the current system theme.
+        let theme = window_context.display.window.theme();
+        window_context.set_theme(theme, self.config.clone());
 
-        self.gl_display = Some(window_context.display.gl_context().display());
+        self.gl_config = Some(window_context.display.gl_context().config());
         self.windows.insert(window_context.id(), window_context);
 
         Ok(())
     }
 
+    /// Create a new terminal window.
+    pub fn create_window(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<Event>,
+        options: WindowOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        let gl_config = self.gl_config.as_ref().unwrap();
+
+        let mut window_context =
+            WindowContext::additional(gl_config, event_loop, self.config.clone(), options)?;
+
+        // Use the colors for the current system theme.
+        let theme = window_context.display.window.theme();
+        window_context.set_theme(theme, self.config.clone());
+
+        self.windows.insert(window_context.id(), window_context);
+
+        Ok(())
+    }
+
+    /// Close a window and release all resources associated with it.
+    ///
+    /// Resources held outside of the window context are released by the handlers of the
+    /// [`EventType::WindowDestroyed`] event emitted afterwards.
+    fn destroy_window(&mut self, window_id: WindowId, event_proxy: &EventLoopProxy<Event>) {
+        // Dropping the context releases its OpenGL resources, including the glyph atlases.
+        if let Some(window_context) = self.windows.remove(&window_id) {
+            window_context.save_geometry();
+            info!("Closed window with ID {}", u64::from(window_id));
+
+            let _ = event_proxy.send_event(Event::new(EventType::WindowDestroyed, window_id));
+        }
+    }
+
     /// Run the event loop.
     ///
     /// The result is exit code generate from the loop.
@@ -171,15 +299,34 @@ impl Processor {
                 return;
             }
 
+            // Associate logs with the window whose event is being processed.
+            logging::set_window_id(Self::event_window_id(&event));
+
             match event {
                 // The event loop just got initialized. Create a window.
                 WinitEvent::Resumed => {
-                    if let Err(err) = self.create_initial_window(event_loop) {
+                    // Creating window inside event loop is required for platforms like macOS to
+                    // properly initialize state, like tab management. Otherwise the first
+                    // window won't handle tabs.
+                    let initial_window_options = match self.initial_window_options.take() {
+                        Some(initial_window_options) => initial_window_options,
+                        None => return,
+                    };
+
+                    if let Err(err) = self.create_initial_window(event_loop, initial_window_options)
+                    {
                         *initial_window_error_loop = Err(err);
                         event_loop.exit();
                         return;
                     }
 
+                    // Create the IPC socket listener once the window exists, without waiting for
+                    // it to be drawn, since occluded windows might never receive a redraw.
+                    #[cfg(unix)]
+                    if self.config.ipc_socket {
+                        self.socket_path = ipc::spawn_ipc_socket(self.socket.take(), proxy.clone());
+                    }
+
                     info!("Initialisation complete");
                 },
                 // NOTE: This event bypasses batching to minimize input latency.
@@ -200,20 +347,30 @@ impl Processor {
                         None => return,
                     };
 
-                    window_context.handle_event(event_loop, &proxy, &mut scheduler, event);
+                    window_context.handle_event(event);
+
+                    if window_context.draw(&mut scheduler) {
+                        self.finish_startup();
+                    }
+                },
+                WinitEvent::WindowEvent { window_id, event: WindowEvent::ThemeChanged(theme) } => {
+                    if let Some(window_context) = self.windows.get_mut(&window_id) {
+                        window_context.set_theme(Some(theme), self.config.clone());
+                    }
+                },
+                WinitEvent::WindowEvent { window_id, event: WindowEvent::CloseRequested } => {
+                    self.destroy_window(window_id, &proxy);
 
-                    window_context.draw(&mut scheduler);
+                    // Shutdown once the last window has been closed.
+                    if self.windows.is_empty() {
+                        event_loop.exit();
+                    }
                 },
                 // Process all pending events.
                 WinitEvent::AboutToWait => {
                     // Dispatch event to all windows.
                     for window_context in self.windows.values_mut() {
-                        window_context.handle_event(
-                            event_loop,
-                            &proxy,
-                            &mut scheduler,
-                            WinitEvent::AboutToWait,
-                        );
+                        window_context.handle_event(WinitEvent::AboutToWait);
                     }
 
                     // Update the scheduler after event processing to ensure
@@ -224,22 +381,89 @@ impl Processor {
                     };
                     event_loop.set_control_flow(control_flow);
                 },
+                // Release everything still associated with a closed window.
+                WinitEvent::UserEvent(Event {
+                    window_id: Some(window_id),
+                    payload: EventType::WindowDestroyed,
+                }) => {
+                    scheduler.unschedule_window(window_id);
+                },
+                // Process config update.
+                WinitEvent::UserEvent(Event { payload: EventType::ConfigReload(path), .. }) => {
+                    // Clear config errors, they will be shown again if they still apply.
+                    for window_context in self.windows.values_mut() {
+                        window_context.clear_config_messages();
+                    }
+
+                    // Load config and update each terminal.
+                    if let Ok(config) = config::reload(&path) {
+                        self.config = Rc::new(config);
+
+                        for window_context in self.windows.values_mut() {
+                            window_context.update_config(self.config.clone());
+                        }
+                    }
+                },
+                // Create a new terminal window.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::CreateWindow(options), ..
+                }) => {
+                    if let Err(err) = self.create_window(event_loop, options) {
+                        error!("Could not open window: {:?}", err);
+                    }
+                },
+                // Process IPC config update.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::IpcConfig(ipc_config), ..
+                }) => {
+                    // Try and parse options as toml.
+                    let options = ParsedOptions::from_options(&ipc_config.options);
+
+                    // Override IPC config for each window with matching ID.
+                    let window_id = ipc_config
+                        .window_id
+                        .and_then(|id| u64::try_from(id).ok())
+                        .map(WindowId::from);
+                    for (_, window_context) in self
+                        .windows
+                        .iter_mut()
+                        .filter(|(id, _)| window_id.is_none() || window_id == Some(**id))
+                    {
+                        if ipc_config.reset {
+                            window_context.reset_window_config(self.config.clone());
+                        } else {
+                            window_context.add_window_config(self.config.clone(), &options);
+                        }
+                    }
+                },
+                // Start capturing frames.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::CaptureFrames(options), ..
+                }) => {
+                    let window_id =
+                        options.window_id.and_then(|id| u64::try_from(id).ok()).map(WindowId::from);
+                    for (_, window_context) in self
+                        .windows
+                        .iter_mut()
+                        .filter(|(id, _)| window_id.is_none() || window_id == Some(**id))
+                    {
+                        window_context.capture_frames(&options);
+                    }
+                },
                 // Process events affecting all windows.
                 WinitEvent::UserEvent(event @ Event { window_id: None, .. }) => {
                     for window_context in self.windows.values_mut() {
-                        window_context.handle_event(
-                            event_loop,
-                            &proxy,
-                            &mut scheduler,
-                            event.clone().into(),
-                        );
+                        window_context.handle_event(event.clone().into());
                     }
                 },
                 // Process window-specific events.
                 WinitEvent::WindowEvent { window_id, .. }
                 | WinitEvent::UserEvent(Event { window_id: Some(window_id), .. }) => {
                     if let Some(window_context) = self.windows.get_mut(&window_id) {
-                        window_context.handle_event(event_loop, &proxy, &mut scheduler, event);
+                        window_context.handle_event(event);
                     }
                 },
                 _ => (),
@@ -253,6 +477,15 @@ impl Processor {
         }
     }
 
+    /// Get the window an event is targeted at.
+    fn event_window_id(event: &WinitEvent<Event>) -> Option<WindowId> {
+        match event {
+            WinitEvent::WindowEvent { window_id, .. } => Some(*window_id),
+            WinitEvent::UserEvent(Event { window_id, .. }) => *window_id,
+            _ => None,
+        }
+    }
+
     /// Check if an event is irrelevant and can be skipped.
     fn skip_event(event: &WinitEvent<Event>) -> bool {
         match event {
@@ -264,8 +497,49 @@ impl Processor {
                     | WindowEvent::HoveredFile(_)
                     | WindowEvent::Moved(_)
             ),
-            WinitEvent::Suspended { .. } | WinitEvent::NewEvents { .. } => true,
+            WinitEvent::Suspended | WinitEvent::NewEvents { .. } => true,
             _ => false,
         }
     }
 }
+
+/// Create the event loop, connecting to the display server.
+pub fn create_event_loop() -> Result<EventLoop<Event>, Box<dyn Error>> {
+    let event_loop = EventLoopBuilder::<Event>::with_user_event().build();
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let event_loop = event_loop.map_err(DisplayServerError);
+
+    Ok(event_loop?)
+}
+
+/// Failure to connect to a display server.
+#[cfg(not(any(target_os = "macos", windows)))]
+struct DisplayServerError(EventLoopError);
+
+#[cfg(not(any(target_os = "macos", windows)))]
+impl FmtDisplay for DisplayServerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Unable to connect to a display server: {}", self.0)?;
+        writeln!(f)?;
+        writeln!(f, "Alacritty needs a running Wayland compositor or X server:")?;
+        writeln!(f, "  - Start Alacritty from within your graphical session")?;
+        writeln!(f, "  - Over SSH, enable X11 forwarding with `ssh -X`")?;
+        write!(f, "  - Check that WAYLAND_DISPLAY or DISPLAY point to a running server")
+    }
+}
+
+// Print the diagnostic as is when it's returned from `main`.
+#[cfg(not(any(target_os = "macos", windows)))]
+impl Debug for DisplayServerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        FmtDisplay::fmt(self, f)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+impl Error for DisplayServerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
@@ -0,0 +1,283 @@
+//! Persistence of window geometry across launches.
+//!
+//! Geometry is stored per configuration profile and monitor layout, so a window is never
+//! restored onto a monitor which is no longer connected.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::monitor::MonitorHandle;
+
+use crate::config::ui_config::Delta;
+use crate::config::window::{Dimensions, StartupMode, WindowConfig};
+use crate::display::Display;
+
+/// Name of the file storing the window geometry.
+const STATE_FILE_NAME: &str = "geometry.json";
+
+/// Geometry of a window at the time it was closed.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WindowGeometry {
+    /// Outer position, when reported by the windowing system.
+    position: Option<(i32, i32)>,
+
+    /// Number of columns in the viewport.
+    columns: usize,
+
+    /// Number of lines in the window, including lines reserved for the message bar.
+    lines: usize,
+
+    /// Whether the window was maximized.
+    maximized: bool,
+
+    /// Whether the window was fullscreen.
+    fullscreen: bool,
+}
+
+impl WindowGeometry {
+    /// Capture the current geometry of a display.
+    pub fn new(display: &Display) -> Self {
+        let window = &display.window;
+        Self {
+            position: window.outer_position().map(|position| (position.x, position.y)),
+            columns: display.size_info.columns(),
+            lines: display.size_info.total_lines(),
+            maximized: window.is_maximized(),
+            fullscreen: window.is_fullscreen(),
+        }
+    }
+
+    /// Replace the startup geometry of a window configuration.
+    pub fn apply(&self, config: &mut WindowConfig) {
+        config.startup_mode = if self.fullscreen {
+            StartupMode::Fullscreen
+        } else if self.maximized {
+            StartupMode::Maximized
+        } else {
+            StartupMode::Windowed
+        };
+        config.position = self.position.map(|(x, y)| Delta { x, y });
+        config.dimensions = Dimensions { columns: self.columns, lines: self.lines };
+    }
+}
+
+/// Name, position and size of a connected monitor.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Monitor {
+    name: Option<String>,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Stored geometry for one profile and monitor layout.
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    profile: Option<String>,
+    layout: Vec<Monitor>,
+    geometry: WindowGeometry,
+}
+
+impl Entry {
+    fn matches(&self, profile: Option<&str>, layout: &[Monitor]) -> bool {
+        self.profile.as_deref() == profile && self.layout == layout
+    }
+}
+
+/// Load the geometry saved for a profile with the current monitor layout.
+pub fn load<E>(
+    event_loop: &EventLoopWindowTarget<E>,
+    profile: Option<&str>,
+) -> Option<WindowGeometry> {
+    let path = match state_file_path() {
+        Ok(path) => path,
+        Err(err) => {
+            warn!("Unable to locate window geometry file: {err}");
+            return None;
+        },
+    };
+
+    let layout = monitor_layout(event_loop.available_monitors());
+    let geometry = read_entries(&path)
+        .into_iter()
+        .find(|entry| entry.matches(profile, &layout))
+        .map(|entry| entry.geometry);
+
+    debug!("Restoring window geometry: {geometry:?}");
+
+    geometry
+}
+
+/// Save the geometry of a display for its profile and the current monitor layout.
+pub fn save(display: &Display, profile: Option<&str>) {
+    let path = match state_file_path() {
+        Ok(path) => path,
+        Err(err) => {
+            warn!("Unable to locate window geometry file: {err}");
+            return;
+        },
+    };
+
+    let layout = monitor_layout(display.window.available_monitors());
+    let geometry = WindowGeometry::new(display);
+
+    let mut entries = read_entries(&path);
+    match entries.iter_mut().find(|entry| entry.matches(profile, &layout)) {
+        Some(entry) => entry.geometry = geometry,
+        None => entries.push(Entry { profile: profile.map(String::from), layout, geometry }),
+    }
+
+    let result = serde_json::to_string_pretty(&entries)
+        .map_err(io::Error::from)
+        .and_then(|json| fs::write(&path, json));
+    if let Err(err) = result {
+        warn!("Unable to save window geometry to {path:?}: {err}");
+    }
+}
+
+/// Read all stored geometry entries.
+fn read_entries(path: &Path) -> Vec<Entry> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => {
+            warn!("Unable to read window geometry from {path:?}: {err}");
+            return Vec::new();
+        },
+    };
+
+    let entries: Vec<Value> = serde_json::from_str(&json).unwrap_or_else(|err| {
+        warn!("Ignoring invalid window geometry file {path:?}: {err}");
+        Vec::new()
+    });
+
+    // Skip entries written in an older format, they are replaced once the window is closed.
+    entries.into_iter().filter_map(|entry| serde_json::from_value(entry).ok()).collect()
+}
+
+/// Get the current monitor layout.
+fn monitor_layout(monitors: impl Iterator<Item = MonitorHandle>) -> Vec<Monitor> {
+    let mut layout: Vec<_> = monitors
+        .map(|monitor| {
+            let (position, size) = (monitor.position(), monitor.size());
+            Monitor {
+                name: monitor.name(),
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+            }
+        })
+        .collect();
+
+    // Monitors are not reported in a stable order.
+    layout.sort_unstable();
+
+    layout
+}
+
+#[cfg(not(windows))]
+fn state_file_path() -> Result<PathBuf, Box<dyn Error>> {
+    let xdg = xdg::BaseDirectories::with_prefix("alacritty")?;
+    Ok(xdg.place_state_file(STATE_FILE_NAME)?)
+}
+
+#[cfg(windows)]
+fn state_file_path() -> Result<PathBuf, Box<dyn Error>> {
+    let directory = dirs::data_local_dir().ok_or("no local data directory")?.join("alacritty");
+    fs::create_dir_all(&directory)?;
+    Ok(directory.join(STATE_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::display::SizeInfo;
+    use crate::message_bar::{Message, MessageBuffer, MessageType};
+
+    fn monitor(name: &str, x: i32) -> Monitor {
+        Monitor { name: Some(name.into()), x, y: 0, width: 1920, height: 1080 }
+    }
+
+    #[test]
+    fn apply_windowed_geometry() {
+        let geometry = WindowGeometry {
+            position: Some((10, 20)),
+            columns: 100,
+            lines: 30,
+            maximized: false,
+            fullscreen: false,
+        };
+
+        let mut config = WindowConfig::default();
+        config.startup_mode = StartupMode::Maximized;
+        geometry.apply(&mut config);
+
+        assert_eq!(config.startup_mode, StartupMode::Windowed);
+        assert_eq!(config.position, Some(Delta { x: 10, y: 20 }));
+        assert_eq!(config.dimensions, Dimensions { columns: 100, lines: 30 });
+    }
+
+    #[test]
+    fn apply_startup_mode() {
+        let mut geometry = WindowGeometry {
+            position: None,
+            columns: 80,
+            lines: 24,
+            maximized: true,
+            fullscreen: false,
+        };
+
+        let mut config = WindowConfig::default();
+        config.position = Some(Delta { x: 1, y: 2 });
+        geometry.apply(&mut config);
+        assert_eq!(config.startup_mode, StartupMode::Maximized);
+        assert_eq!(config.position, None);
+
+        // Fullscreen takes precedence over maximized.
+        geometry.fullscreen = true;
+        geometry.apply(&mut config);
+        assert_eq!(config.startup_mode, StartupMode::Fullscreen);
+    }
+
+    #[test]
+    fn entry_matches_profile_and_layout() {
+        let geometry = WindowGeometry {
+            position: None,
+            columns: 80,
+            lines: 24,
+            maximized: false,
+            fullscreen: false,
+        };
+        let layout = vec![monitor("DP-1", 0), monitor("HDMI-1", 1920)];
+        let entry = Entry { profile: Some("work".into()), layout: layout.clone(), geometry };
+
+        assert!(entry.matches(Some("work"), &layout));
+        assert!(!entry.matches(None, &layout));
+        assert!(!entry.matches(Some("home"), &layout));
+        assert!(!entry.matches(Some("work"), &layout[..1]));
+        assert!(!entry.matches(Some("work"), &[monitor("DP-1", 0), monitor("HDMI-1", 0)]));
+    }
+
+    #[test]
+    fn lines_include_message_bar() {
+        let mut size_info = SizeInfo::new(800., 600., 10., 20., 0., 0., false);
+        assert_eq!(size_info.total_lines(), 30);
+
+        // Closing with a visible message must not shrink the restored window.
+        let mut message_buffer = MessageBuffer::default();
+        message_buffer.push(Message::new("error".into(), MessageType::Error));
+        let message_bar_lines = message_buffer.message().map_or(0, |m| m.text(&size_info).len());
+        size_info.reserve_lines(message_bar_lines);
+
+        assert_eq!(size_info.screen_lines(), 29);
+        assert_eq!(size_info.total_lines(), 30);
+    }
+}
@@ -0,0 +1,214 @@
+//! Logging target for Alacritty.
+//!
+//! The main executable is supposed to call `initialize()` exactly once during
+//! startup. All logging messages are written to stderr, in the format requested
+//! through the CLI. Errors and warnings are also shown in the message bar, once
+//! the event loop has been registered with `set_event_proxy()`.
+
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde_json::json;
+use winit::event_loop::EventLoopProxy;
+use winit::window::WindowId;
+
+use crate::cli::Options;
+use crate::event::{Event, EventType};
+use crate::message_bar::{Message, MessageType};
+use crate::renderer::LOG_TARGET_GL;
+
+/// Crates whose logs are shown below trace level.
+const ALLOWED_TARGETS: &[&str] = &["alacritty", "crossfont"];
+
+/// Targets whose errors and warnings are never shown in the message bar.
+const LOG_ONLY_TARGETS: &[&str] = &[LOG_TARGET_GL];
+
+/// Event loop receiving errors and warnings for the message bar.
+static EVENT_PROXY: Mutex<Option<EventLoopProxy<Event>>> = Mutex::new(None);
+
+thread_local! {
+    /// Window whose event is currently being processed.
+    static WINDOW_ID: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Format of log records.
+#[derive(ValueEnum, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+
+    /// One JSON object per line.
+    Json,
+}
+
+/// Initialize the logger to its defaults.
+pub fn initialize(options: &Options) -> Result<(), SetLoggerError> {
+    log::set_max_level(options.log_level());
+
+    let logger = Logger::new(options.log_format);
+    log::set_boxed_logger(Box::new(logger))
+}
+
+/// Forward errors and warnings to the message bar of the windows in an event loop.
+pub fn set_event_proxy(event_proxy: EventLoopProxy<Event>) {
+    *EVENT_PROXY.lock().unwrap() = Some(event_proxy);
+}
+
+/// Set the window associated with log records on the current thread.
+pub fn set_window_id(window_id: Option<WindowId>) {
+    WINDOW_ID.with(|cell| cell.set(window_id.map(u64::from)));
+}
+
+struct Logger {
+    format: LogFormat,
+    start: Instant,
+}
+
+impl Logger {
+    fn new(format: LogFormat) -> Self {
+        Self { format, start: Instant::now() }
+    }
+
+    /// Format a record as a human readable line.
+    fn text_record(&self, record: &Record<'_>) -> String {
+        let elapsed = self.start.elapsed();
+        format!(
+            "[{}.{:0>9}s] [{:<5}] [{}] {}\n",
+            elapsed.as_secs(),
+            elapsed.subsec_nanos(),
+            record.level(),
+            record.target(),
+            record.args(),
+        )
+    }
+
+    /// Format a record as a JSON line.
+    fn json_record(record: &Record<'_>) -> String {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut line = json!({
+            "timestamp": timestamp.as_secs_f64(),
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "message": record.args().to_string(),
+        });
+
+        if let Some(window_id) = WINDOW_ID.with(Cell::get) {
+            line["window_id"] = window_id.into();
+        }
+
+        format!("{line}\n")
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        // Skip logs from dependencies below trace level.
+        if !self.enabled(record.metadata()) || !is_allowed_target(record.level(), record.target()) {
+            return;
+        }
+
+        let line = match self.format {
+            LogFormat::Text => self.text_record(record),
+            LogFormat::Json => Self::json_record(record),
+        };
+
+        let _ = io::stderr().write_all(line.as_bytes());
+
+        send_message(record);
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Show an error or warning in the message bar.
+///
+/// Records logged while handling a window's events are only shown in that window.
+fn send_message(record: &Record<'_>) {
+    let message_type = match record.level() {
+        Level::Error => MessageType::Error,
+        Level::Warn => MessageType::Warning,
+        _ => return,
+    };
+
+    if LOG_ONLY_TARGETS.contains(&record.target()) {
+        return;
+    }
+
+    // Release the lock before sending, in case sending the event logs itself.
+    let event_proxy = match EVENT_PROXY.lock().unwrap().clone() {
+        Some(event_proxy) => event_proxy,
+        None => return,
+    };
+
+    let text = format!("[{}] {}", record.level(), record.args());
+    let mut message = Message::new(text, message_type);
+    message.set_target(record.target().to_owned());
+
+    let window_id = WINDOW_ID.with(Cell::get).map(WindowId::from);
+    let _ = event_proxy.send_event(Event::new(EventType::Message(message), window_id));
+}
+
+/// Check if log messages from a crate should be logged.
+fn is_allowed_target(level: Level, target: &str) -> bool {
+    match (level, log::max_level()) {
+        (Level::Error, LevelFilter::Trace) | (Level::Warn, LevelFilter::Trace) => true,
+        _ => {
+            let crate_name = target.split("::").next().unwrap_or(target);
+            ALLOWED_TARGETS.contains(&crate_name)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::Value;
+
+    /// Format a record as JSON and parse the result.
+    fn json_line(message: &str) -> Value {
+        let line = Logger::json_record(
+            &Record::builder()
+                .args(format_args!("{message}"))
+                .level(Level::Warn)
+                .target("alacritty::test")
+                .build(),
+        );
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.lines().count(), 1);
+
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn json_record_fields() {
+        set_window_id(None);
+        let line = json_line("Some \"quoted\"\nmessage\\");
+
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], "alacritty::test");
+        assert_eq!(line["message"], "Some \"quoted\"\nmessage\\");
+        assert!(line["timestamp"].as_f64().unwrap() > 0.);
+        assert!(line.get("window_id").is_none());
+    }
+
+    #[test]
+    fn json_record_window_id() {
+        set_window_id(Some(WindowId::from(42)));
+        let line = json_line("message");
+        set_window_id(None);
+
+        assert_eq!(line["window_id"], 42);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod locale;
 pub mod proc;
+pub mod secure_input;
@@ -0,0 +1,21 @@
+//! Secure keyboard entry, which prevents other processes from reading keyboard input.
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn EnableSecureEventInput() -> i32;
+    fn DisableSecureEventInput() -> i32;
+}
+
+/// Enable or disable secure keyboard entry.
+///
+/// Secure keyboard entry is reference counted by the system, so every call enabling it must be
+/// balanced by a call disabling it again.
+pub fn set_enabled(enabled: bool) {
+    unsafe {
+        if enabled {
+            EnableSecureEventInput();
+        } else {
+            DisableSecureEventInput();
+        }
+    }
+}
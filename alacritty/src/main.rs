@@ -12,18 +12,30 @@
 #[cfg(not(any(feature = "x11", feature = "wayland", target_os = "macos", windows)))]
 compile_error!(r#"at least one of the "x11"/"wayland" features must be enabled"#);
 
+#[cfg(unix)]
+use std::env;
 use std::error::Error;
+#[cfg(unix)]
+use std::fs;
+use std::time::Instant;
+
+use log::{debug, info};
 
 #[cfg(windows)]
 use windows_sys::Win32::System::Console::{AttachConsole, FreeConsole, ATTACH_PARENT_PROCESS};
-use winit::event_loop::EventLoopBuilder as WinitEventLoopBuilder;
-#[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
-use winit::platform::x11::EventLoopWindowTargetExtX11;
 
+mod cli;
+mod config;
+mod debug_info;
 mod display;
 mod event;
+mod geometry;
+#[cfg(unix)]
+mod ipc;
+mod logging;
 #[cfg(target_os = "macos")]
 mod macos;
+mod message_bar;
 mod renderer;
 mod scheduler;
 mod window_context;
@@ -33,29 +45,78 @@ mod gl {
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
 }
 
-use crate::event::{Event, Processor};
+#[cfg(unix)]
+use crate::cli::{MessageOptions, SocketMessage};
+use crate::cli::{Options, Subcommands};
+use crate::config::{monitor, UiConfig};
+use crate::event::Processor;
 #[cfg(target_os = "macos")]
 use crate::macos::locale;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    alacritty()?;
+    // Load command line options.
+    let mut options = Options::new();
+
+    // Initialize the logger as soon as possible as to capture output from other subsystems.
+    logging::initialize(&options)?;
+
+    // Handle command line subcommands.
+    match options.subcommands.take() {
+        #[cfg(unix)]
+        Some(Subcommands::Msg(options)) => {
+            msg(options)?;
+            return Ok(());
+        },
+        Some(Subcommands::DebugInfo) => {
+            debug_info::print(&options)?;
+            return Ok(());
+        },
+        None => (),
+    }
+
+    alacritty(options)?;
     Ok(())
 }
 
+/// `msg` subcommand entrypoint.
+#[cfg(unix)]
+fn msg(mut options: MessageOptions) -> Result<(), Box<dyn Error>> {
+    // Resolve paths relative to this process, since the socket owner has a different directory.
+    if let SocketMessage::CaptureFrames(capture) = &mut options.message {
+        capture.directory = env::current_dir()?.join(&capture.directory);
+    }
+
+    ipc::send_message(options.socket, options.message).map_err(|err| err.into())
+}
+
 /// Run main Alacritty entrypoint.
 ///
 /// Creates a window, the terminal state, PTY, I/O event loop, input processor,
 /// config change monitor, and runs the main display loop.
-fn alacritty() -> Result<(), Box<dyn Error>> {
+fn alacritty(options: Options) -> Result<(), Box<dyn Error>> {
     // Setup winit event loop.
-    let window_event_loop = WinitEventLoopBuilder::<Event>::with_user_event().build()?;
+    let window_event_loop = event::create_event_loop()?;
+
+    // Show errors and warnings in the message bar.
+    logging::set_event_proxy(window_event_loop.create_proxy());
+
+    // Load configuration file.
+    let config_start = Instant::now();
+    let config = config::load(&options);
+    debug!("Configuration loaded in {:?}", config_start.elapsed());
+    log_config_path(&config);
 
     // Set macOS locale.
     #[cfg(target_os = "macos")]
     locale::set_locale_environment();
 
+    // Monitor config file for changes.
+    if config.live_config_reload {
+        monitor::watch(config.config_paths.clone(), window_event_loop.create_proxy());
+    }
+
     // Event processor.
-    let mut processor = Processor::new(&window_event_loop);
+    let mut processor = Processor::new(config, &options, &window_event_loop);
 
     // Start event loop and block until shutdown.
     let result = processor.run(window_event_loop);
@@ -71,8 +132,16 @@ fn alacritty() -> Result<(), Box<dyn Error>> {
     // order.
     //
     // FIXME: Change PTY API to enforce the correct drop order with the typesystem.
+    #[cfg(unix)]
+    let socket_path = processor.socket_path.take();
     drop(processor);
 
+    // Clean up the IPC socket file.
+    #[cfg(unix)]
+    if let Some(socket_path) = socket_path {
+        let _ = fs::remove_file(socket_path);
+    }
+
     // Without explicitly detaching the console cmd won't redraw it's prompt.
     #[cfg(windows)]
     unsafe {
@@ -81,3 +150,16 @@ fn alacritty() -> Result<(), Box<dyn Error>> {
 
     result
 }
+
+fn log_config_path(config: &UiConfig) {
+    if config.config_paths.is_empty() {
+        return;
+    }
+
+    let mut msg = String::from("Configuration files loaded from:");
+    for path in &config.config_paths {
+        msg.push_str(&format!("\n  {:?}", path.display()));
+    }
+
+    info!("{}", msg);
+}
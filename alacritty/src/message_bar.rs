@@ -0,0 +1,307 @@
+use std::collections::VecDeque;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::display::SizeInfo;
+
+pub const CLOSE_BUTTON_TEXT: &str = "[X]";
+const CLOSE_BUTTON_PADDING: usize = 1;
+const MIN_FREE_LINES: usize = 3;
+const TRUNCATED_MESSAGE: &str = "[MESSAGE TRUNCATED]";
+
+/// Message for display in the MessageBuffer.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Message {
+    text: String,
+    ty: MessageType,
+    target: Option<String>,
+}
+
+/// Purpose of the message.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum MessageType {
+    /// A message represents an error.
+    Error,
+
+    /// A message represents a warning.
+    Warning,
+}
+
+impl Message {
+    /// Create a new message.
+    pub fn new(text: String, ty: MessageType) -> Message {
+        Message { text, ty, target: None }
+    }
+
+    /// Formatted message text lines.
+    pub fn text(&self, size_info: &SizeInfo) -> Vec<String> {
+        let num_cols = size_info.columns();
+        let total_lines =
+            (size_info.height() - 2. * size_info.padding_y()) / size_info.cell_height();
+        let max_lines = (total_lines as usize).saturating_sub(MIN_FREE_LINES);
+        let button_len = CLOSE_BUTTON_TEXT.chars().count();
+
+        // Split line to fit the screen.
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut line_len = 0;
+        for c in self.text.trim().chars() {
+            if c == '\n'
+                || line_len == num_cols
+                // Keep space in first line for button.
+                || (lines.is_empty()
+                    && num_cols >= button_len
+                    && line_len == num_cols.saturating_sub(button_len + CLOSE_BUTTON_PADDING))
+            {
+                let is_whitespace = c.is_whitespace();
+
+                // Attempt to wrap on word boundaries.
+                let mut new_line = String::new();
+                if let Some(index) = line.rfind(char::is_whitespace).filter(|_| !is_whitespace) {
+                    let split = line.split_off(index + 1);
+                    line.pop();
+                    new_line = split;
+                }
+
+                lines.push(Self::pad_text(line, num_cols));
+                line = new_line;
+                line_len = line.chars().count();
+
+                // Do not append whitespace at EOL.
+                if is_whitespace {
+                    continue;
+                }
+            }
+
+            line.push(c);
+
+            // Reserve extra column for fullwidth characters.
+            let width = c.width().unwrap_or(0);
+            if width == 2 {
+                line.push(' ');
+            }
+
+            line_len += width
+        }
+        lines.push(Self::pad_text(line, num_cols));
+
+        // Truncate output if it's too long.
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            if TRUNCATED_MESSAGE.len() <= num_cols {
+                if let Some(line) = lines.iter_mut().last() {
+                    *line = Self::pad_text(TRUNCATED_MESSAGE.into(), num_cols);
+                }
+            }
+        }
+
+        // Append close button to first line.
+        if button_len <= num_cols {
+            if let Some(line) = lines.get_mut(0) {
+                line.truncate(num_cols - button_len);
+                line.push_str(CLOSE_BUTTON_TEXT);
+            }
+        }
+
+        lines
+    }
+
+    /// Message type.
+    #[inline]
+    pub fn ty(&self) -> MessageType {
+        self.ty
+    }
+
+    /// Message target.
+    #[inline]
+    pub fn target(&self) -> Option<&String> {
+        self.target.as_ref()
+    }
+
+    /// Update the message target.
+    #[inline]
+    pub fn set_target(&mut self, target: String) {
+        self.target = Some(target);
+    }
+
+    /// Right-pad text to fit a specific number of columns.
+    #[inline]
+    fn pad_text(mut text: String, num_cols: usize) -> String {
+        let text_len = text.chars().count();
+        for _ in text_len..num_cols {
+            text.push(' ');
+        }
+        text
+    }
+}
+
+/// Storage for message bar.
+#[derive(Debug, Default)]
+pub struct MessageBuffer {
+    messages: VecDeque<Message>,
+}
+
+impl MessageBuffer {
+    /// Current message.
+    #[inline]
+    pub fn message(&self) -> Option<&Message> {
+        self.messages.front()
+    }
+
+    /// Remove all messages with a specific target.
+    #[inline]
+    pub fn remove_target(&mut self, target: &str) {
+        self.messages = self
+            .messages
+            .drain(..)
+            .filter(|m| m.target().map(String::as_str) != Some(target))
+            .collect();
+    }
+
+    /// Add a new message to the queue.
+    #[inline]
+    pub fn push(&mut self, message: Message) {
+        self.messages.push_back(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Message, MessageBuffer, MessageType, MIN_FREE_LINES};
+    use crate::display::SizeInfo;
+
+    /// Formatted text of a single error message.
+    fn text(input: &str, size: SizeInfo) -> Vec<String> {
+        let mut message_buffer = MessageBuffer::default();
+        message_buffer.push(Message::new(input.into(), MessageType::Error));
+        message_buffer.message().unwrap().text(&size)
+    }
+
+    #[test]
+    fn appends_close_button() {
+        let size = SizeInfo::new(7., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("a", size), vec![String::from("a   [X]")]);
+    }
+
+    #[test]
+    fn multiline_close_button_first_line() {
+        let size = SizeInfo::new(6., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("fo\nbar", size), vec![String::from("fo [X]"), String::from("bar   ")]);
+    }
+
+    #[test]
+    fn splits_on_newline() {
+        let size = SizeInfo::new(6., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("a\nb", size).len(), 2);
+    }
+
+    #[test]
+    fn splits_on_length() {
+        let size = SizeInfo::new(6., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("foobar1", size).len(), 2);
+    }
+
+    #[test]
+    fn empty_with_shortterm() {
+        let size = SizeInfo::new(6., 0., 1., 1., 0., 0., false);
+
+        assert_eq!(text("foobar", size).len(), 0);
+    }
+
+    #[test]
+    fn truncates_long_messages() {
+        let input = "hahahahahahahahahahaha truncate this because it's too long for the term";
+        let size = SizeInfo::new(22., (MIN_FREE_LINES + 2) as f32, 1., 1., 0., 0., false);
+
+        assert_eq!(text(input, size), vec![
+            String::from("hahahahahahahahaha [X]"),
+            String::from("[MESSAGE TRUNCATED]   ")
+        ]);
+    }
+
+    #[test]
+    fn hide_button_when_too_narrow() {
+        let size = SizeInfo::new(2., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("ha", size), vec![String::from("ha")]);
+    }
+
+    #[test]
+    fn hide_truncated_when_too_narrow() {
+        let size = SizeInfo::new(2., (MIN_FREE_LINES + 2) as f32, 1., 1., 0., 0., false);
+
+        assert_eq!(text("hahahahahahahahaha", size), vec![String::from("ha"), String::from("ha")]);
+    }
+
+    #[test]
+    fn add_newline_for_button() {
+        let size = SizeInfo::new(5., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("test", size), vec![String::from("t [X]"), String::from("est  ")]);
+    }
+
+    #[test]
+    fn wrap_on_words() {
+        let size = SizeInfo::new(5., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("a\nbc defg", size), vec![
+            String::from("a [X]"),
+            String::from("bc   "),
+            String::from("defg ")
+        ]);
+    }
+
+    #[test]
+    fn wrap_with_unicode() {
+        let size = SizeInfo::new(7., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("ab\nc 👩d fgh", size), vec![
+            String::from("ab  [X]"),
+            String::from("c 👩 d  "),
+            String::from("fgh    ")
+        ]);
+    }
+
+    #[test]
+    fn strip_whitespace_at_linebreak() {
+        let size = SizeInfo::new(3., 10., 1., 1., 0., 0., false);
+
+        assert_eq!(text("\n0 1 2 3", size), vec![
+            String::from("[X]"),
+            String::from("0 1"),
+            String::from("2 3"),
+        ]);
+    }
+
+    #[test]
+    fn ignores_padding() {
+        let size = SizeInfo::new(9., 14., 1., 1., 1., 2., false);
+
+        assert_eq!(text("a", size), vec![String::from("a   [X]")]);
+    }
+
+    #[test]
+    fn remove_target() {
+        let mut message_buffer = MessageBuffer::default();
+        for i in 0..10 {
+            let mut msg = Message::new(i.to_string(), MessageType::Error);
+            if i % 2 == 0 && i < 5 {
+                msg.set_target("target".into());
+            } else if i % 3 == 0 {
+                msg.set_target("other".into());
+            }
+            message_buffer.push(msg);
+        }
+
+        message_buffer.remove_target("target");
+
+        let texts: Vec<_> = message_buffer.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["1", "3", "5", "6", "7", "8", "9"]);
+        assert_eq!(message_buffer.message().unwrap().target(), None);
+        assert_eq!(message_buffer.messages[1].target(), Some(&String::from("other")));
+    }
+}
@@ -8,20 +8,52 @@ use std::{fmt, ptr};
 use ahash::RandomState;
 use glutin::context::PossiblyCurrentContext;
 use glutin::display::{GetGlDisplay, GlDisplay};
-use log::{debug, error, info, warn, LevelFilter};
+use log::{debug, info, warn, LevelFilter};
 
-use crate::display::Rgb;
-use crate::display::SizeInfo;
+use crate::display::{Rgb, SizeInfo};
 use crate::gl;
 use crate::renderer::rects::{RectRenderer, RenderRect};
 use crate::renderer::shader::ShaderError;
+use crate::renderer::text::glyph_cache::GlyphCache;
+use crate::renderer::text::{Glsl3Renderer, LoaderApi, RenderableCell};
 
 pub mod platform;
 pub mod rects;
 mod shader;
+pub mod text;
 
 use shader::ShaderVersion;
 
+macro_rules! cstr {
+    ($s:literal) => {
+        // This can be optimized into an no-op with pre-allocated NUL-terminated bytes.
+        unsafe { std::ffi::CStr::from_ptr(concat!($s, "\0").as_ptr().cast()) }
+    };
+}
+pub(crate) use cstr;
+
+/// Run an OpenGL call, logging all errors it raised in debug builds.
+macro_rules! gl_call {
+    ($call:expr) => {{
+        let result = $call;
+        #[cfg(debug_assertions)]
+        $crate::renderer::log_gl_errors(stringify!($call), file!(), line!());
+        result
+    }};
+}
+pub(crate) use gl_call;
+
+/// Logging target for OpenGL errors.
+///
+/// Errors on this target are not shown in the message bar, since a broken call is usually
+/// repeated on every frame.
+pub const LOG_TARGET_GL: &str = "alacritty::renderer::gl";
+
+/// Maximum number of errors drained after a single OpenGL call.
+///
+/// Some drivers keep reporting errors like `GL_CONTEXT_LOST` forever, so this prevents a hang.
+const MAX_GL_ERRORS: usize = 8;
+
 /// Whether the OpenGL functions have been loaded.
 pub static GL_FUNS_LOADED: AtomicBool = AtomicBool::new(false);
 
@@ -70,6 +102,7 @@ impl From<String> for Error {
 
 #[derive(Debug)]
 pub struct Renderer {
+    text_renderer: Glsl3Renderer,
     rect_renderer: RectRenderer,
 }
 
@@ -92,11 +125,16 @@ fn gl_get_string(
     }
 }
 
-impl Renderer {
-    /// Create a new renderer.
-    ///
-    /// This will automatically pick between the GLES2 and GLSL3 renderer based on the GPU's
-    /// supported OpenGL version.
+/// Details about the OpenGL implementation.
+#[derive(Debug)]
+pub struct GlInfo {
+    pub renderer: Cow<'static, str>,
+    pub version: Cow<'static, str>,
+    pub shader_version: Cow<'static, str>,
+}
+
+impl GlInfo {
+    /// Query the OpenGL implementation of the current context.
     pub fn new(context: &PossiblyCurrentContext) -> Result<Self, Error> {
         // We need to load OpenGL functions once per instance, but only after we make our context
         // current due to WGL limitations.
@@ -108,9 +146,21 @@ impl Renderer {
             });
         }
 
-        let shader_version = gl_get_string(gl::SHADING_LANGUAGE_VERSION, "shader version")?;
-        let gl_version = gl_get_string(gl::VERSION, "OpenGL version")?;
-        let renderer = gl_get_string(gl::RENDERER, "renderer version")?;
+        Ok(Self {
+            shader_version: gl_get_string(gl::SHADING_LANGUAGE_VERSION, "shader version")?,
+            version: gl_get_string(gl::VERSION, "OpenGL version")?,
+            renderer: gl_get_string(gl::RENDERER, "renderer version")?,
+        })
+    }
+}
+
+impl Renderer {
+    /// Create a new renderer.
+    ///
+    /// This will automatically pick between the GLES2 and GLSL3 renderer based on the GPU's
+    /// supported OpenGL version.
+    pub fn new(context: &PossiblyCurrentContext) -> Result<Self, Error> {
+        let GlInfo { renderer, version: gl_version, shader_version } = GlInfo::new(context)?;
 
         info!("Running on {renderer}");
         info!("OpenGL version {gl_version}, shader_version {shader_version}");
@@ -121,18 +171,60 @@ impl Renderer {
         } else {
             RectRenderer::new(ShaderVersion::Gles2)?
         };
+        let text_renderer = Glsl3Renderer::new()?;
 
         // Enable debug logging for OpenGL as well.
         if log::max_level() >= LevelFilter::Debug && GlExtensions::contains("GL_KHR_debug") {
             debug!("Enabled debug logging for OpenGL");
             unsafe {
-                gl::Enable(gl::DEBUG_OUTPUT);
-                gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
-                gl::DebugMessageCallback(Some(gl_debug_log), ptr::null_mut());
+                gl_call!(gl::Enable(gl::DEBUG_OUTPUT));
+                gl_call!(gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS));
+                gl_call!(gl::DebugMessageCallback(Some(gl_debug_log), ptr::null_mut()));
             }
         }
 
-        Ok(Self { rect_renderer })
+        Ok(Self { text_renderer, rect_renderer })
+    }
+
+    pub fn draw_cells<I: Iterator<Item = RenderableCell>>(
+        &mut self,
+        size_info: &SizeInfo,
+        glyph_cache: &mut GlyphCache,
+        cells: I,
+    ) {
+        self.text_renderer.draw_cells(size_info, glyph_cache, cells)
+    }
+
+    /// Draw a string in a variable location. Used for printing the render timer.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_string(
+        &mut self,
+        line: usize,
+        column: usize,
+        fg: Rgb,
+        bg: Rgb,
+        bg_alpha: f32,
+        string_chars: impl Iterator<Item = char>,
+        size_info: &SizeInfo,
+        glyph_cache: &mut GlyphCache,
+    ) {
+        let cells = string_chars.enumerate().map(|(i, character)| RenderableCell {
+            line,
+            column: column + i,
+            character,
+            bg_alpha,
+            fg,
+            bg,
+        });
+
+        self.draw_cells(size_info, glyph_cache, cells);
+    }
+
+    pub fn with_loader<F, T>(&mut self, func: F) -> T
+    where
+        F: FnOnce(LoaderApi<'_>) -> T,
+    {
+        self.text_renderer.with_loader(func)
     }
 
     /// Draw all rectangles simultaneously to prevent excessive program swaps.
@@ -144,8 +236,15 @@ impl Renderer {
         // Prepare rect rendering state.
         unsafe {
             // Remove padding from viewport.
-            gl::Viewport(0, 0, size_info.width() as i32, size_info.height() as i32);
-            gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
+            gl_call!(gl::Viewport(0, 0, size_info.width() as i32, size_info.height() as i32));
+            // Blend alpha as premultiplied `over`, so translucent rects don't punch holes into
+            // transparent windows.
+            gl_call!(gl::BlendFuncSeparate(
+                gl::SRC_ALPHA,
+                gl::ONE_MINUS_SRC_ALPHA,
+                gl::ONE,
+                gl::ONE_MINUS_SRC_ALPHA,
+            ));
         }
 
         self.rect_renderer.draw(size_info, rects);
@@ -153,7 +252,7 @@ impl Renderer {
         // Activate regular state again.
         unsafe {
             // Reset blending strategy.
-            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+            gl_call!(gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR));
 
             // Restore viewport with padding.
             self.set_viewport(size_info);
@@ -163,19 +262,37 @@ impl Renderer {
     /// Fill the window with `color` and `alpha`.
     pub fn clear(&self, color: Rgb, alpha: f32) {
         unsafe {
-            gl::ClearColor(
+            gl_call!(gl::ClearColor(
                 (f32::from(color.r) / 255.0).min(1.0) * alpha,
                 (f32::from(color.g) / 255.0).min(1.0) * alpha,
                 (f32::from(color.b) / 255.0).min(1.0) * alpha,
                 alpha,
-            );
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+            ));
+            gl_call!(gl::Clear(gl::COLOR_BUFFER_BIT));
         }
     }
 
+    /// Read the RGBA pixels of the frame being drawn, starting with the bottom row.
+    pub fn read_pixels(&self, width: u32, height: u32) -> Vec<u8> {
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        unsafe {
+            gl_call!(gl::PixelStorei(gl::PACK_ALIGNMENT, 1));
+            gl_call!(gl::ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            ));
+        }
+        pixels
+    }
+
     pub fn finish(&self) {
         unsafe {
-            gl::Finish();
+            gl_call!(gl::Finish());
         }
     }
 
@@ -183,9 +300,54 @@ impl Renderer {
     #[inline]
     pub fn set_viewport(&self, size: &SizeInfo) {
         unsafe {
-            gl::Viewport(0 as i32, 0 as i32, size.width() as i32, size.height() as i32);
+            gl_call!(gl::Viewport(
+                size.padding_x() as i32,
+                size.padding_y() as i32,
+                size.width() as i32 - 2 * size.padding_x() as i32,
+                size.height() as i32 - 2 * size.padding_y() as i32,
+            ));
         }
     }
+
+    /// Resize the renderer.
+    pub fn resize(&self, size_info: &SizeInfo) {
+        self.set_viewport(size_info);
+        self.text_renderer.resize(size_info);
+    }
+}
+
+/// Log all pending OpenGL errors, together with the call which raised them.
+#[cfg(debug_assertions)]
+pub fn log_gl_errors(call: &str, file: &str, line: u32) {
+    for _ in 0..MAX_GL_ERRORS {
+        let error = unsafe { gl::GetError() };
+        let name = match error {
+            gl::NO_ERROR => return,
+            gl::INVALID_ENUM => "GL_INVALID_ENUM",
+            gl::INVALID_VALUE => "GL_INVALID_VALUE",
+            gl::INVALID_OPERATION => "GL_INVALID_OPERATION",
+            gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+            gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+            gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+            gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+            _ => "unknown error",
+        };
+        warn!(target: LOG_TARGET_GL, "{name} ({error:#x}) at {file}:{line}: {call}");
+    }
+}
+
+/// Discard all pending OpenGL errors, returning whether there were any.
+///
+/// This is used after calls which are expected to fail on some drivers.
+pub fn discard_gl_errors() -> bool {
+    let mut discarded = false;
+    for _ in 0..MAX_GL_ERRORS {
+        if unsafe { gl::GetError() } == gl::NO_ERROR {
+            break;
+        }
+        discarded = true;
+    }
+    discarded
 }
 
 struct GlExtensions;
@@ -206,12 +368,16 @@ impl GlExtensions {
             let extensions = gl::GetString(gl::EXTENSIONS);
 
             if extensions.is_null() {
+                // Core profiles reject `GL_EXTENSIONS` for `glGetString`, which is expected.
+                discard_gl_errors();
+
                 let mut extensions_number = 0;
-                gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut extensions_number);
+                gl_call!(gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut extensions_number));
 
                 (0..extensions_number as gl::types::GLuint)
                     .flat_map(|i| {
-                        let extension = CStr::from_ptr(gl::GetStringi(gl::EXTENSIONS, i) as *mut _);
+                        let extension = gl_call!(gl::GetStringi(gl::EXTENSIONS, i));
+                        let extension = CStr::from_ptr(extension as *mut _);
                         extension.to_str()
                     })
                     .collect()
@@ -237,9 +403,9 @@ extern "system" fn gl_debug_log(
     let msg = unsafe { CStr::from_ptr(msg).to_string_lossy() };
     match kind {
         gl::DEBUG_TYPE_ERROR | gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => {
-            error!("[gl_render] {}", msg)
+            warn!(target: LOG_TARGET_GL, "[gl_render] {}", msg)
         },
-        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => warn!("[gl_render] {}", msg),
-        _ => debug!("[gl_render] {}", msg),
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => warn!(target: LOG_TARGET_GL, "[gl_render] {}", msg),
+        _ => debug!(target: LOG_TARGET_GL, "[gl_render] {}", msg),
     }
 }
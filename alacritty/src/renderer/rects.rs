@@ -2,12 +2,11 @@ use std::mem;
 
 use log::info;
 
-use crate::display::Rgb;
-use crate::display::SizeInfo;
+use crate::display::{Rgb, SizeInfo};
 use crate::gl;
 use crate::gl::types::*;
 use crate::renderer::shader::{ShaderError, ShaderProgram, ShaderVersion};
-use crate::renderer::{self};
+use crate::renderer::{self, gl_call};
 
 #[derive(Debug, Copy, Clone)]
 pub struct RenderRect {
@@ -86,42 +85,42 @@ impl RectRenderer {
 
         unsafe {
             // Allocate buffers.
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
+            gl_call!(gl::GenVertexArrays(1, &mut vao));
+            gl_call!(gl::GenBuffers(1, &mut vbo));
 
-            gl::BindVertexArray(vao);
+            gl_call!(gl::BindVertexArray(vao));
 
             // VBO binding is not part of VAO itself, but VBO binding is stored in attributes.
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl_call!(gl::BindBuffer(gl::ARRAY_BUFFER, vbo));
 
             let mut attribute_offset = 0;
 
             // Position.
-            gl::VertexAttribPointer(
+            gl_call!(gl::VertexAttribPointer(
                 0,
                 2,
                 gl::FLOAT,
                 gl::FALSE,
                 mem::size_of::<Vertex>() as i32,
                 attribute_offset as *const _,
-            );
-            gl::EnableVertexAttribArray(0);
+            ));
+            gl_call!(gl::EnableVertexAttribArray(0));
             attribute_offset += mem::size_of::<f32>() * 2;
 
             // Color.
-            gl::VertexAttribPointer(
+            gl_call!(gl::VertexAttribPointer(
                 1,
                 4,
                 gl::UNSIGNED_BYTE,
                 gl::TRUE,
                 mem::size_of::<Vertex>() as i32,
                 attribute_offset as *const _,
-            );
-            gl::EnableVertexAttribArray(1);
+            ));
+            gl_call!(gl::EnableVertexAttribArray(1));
 
             // Reset buffer bindings.
-            gl::BindVertexArray(0);
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl_call!(gl::BindVertexArray(0));
+            gl_call!(gl::BindBuffer(gl::ARRAY_BUFFER, 0));
         }
 
         let programs = [rect_program, undercurl_program, dotted_program, dashed_program];
@@ -131,10 +130,10 @@ impl RectRenderer {
     pub fn draw(&mut self, size_info: &SizeInfo, rects: Vec<RenderRect>) {
         unsafe {
             // Bind VAO to enable vertex attribute slots.
-            gl::BindVertexArray(self.vao);
+            gl_call!(gl::BindVertexArray(self.vao));
 
             // Bind VBO only once for buffer data upload only.
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl_call!(gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo));
         }
 
         let half_width = size_info.width() / 2.;
@@ -156,26 +155,26 @@ impl RectRenderer {
                 }
 
                 let program = &self.programs[rect_kind as usize];
-                gl::UseProgram(program.id());
+                gl_call!(gl::UseProgram(program.id()));
 
                 // Upload accumulated undercurl vertices.
-                gl::BufferData(
+                gl_call!(gl::BufferData(
                     gl::ARRAY_BUFFER,
                     (vertices.len() * mem::size_of::<Vertex>()) as isize,
                     vertices.as_ptr() as *const _,
                     gl::STREAM_DRAW,
-                );
+                ));
 
                 // Draw all vertices as list of triangles.
-                gl::DrawArrays(gl::TRIANGLES, 0, vertices.len() as i32);
+                gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, vertices.len() as i32));
             }
 
             // Disable program.
-            gl::UseProgram(0);
+            gl_call!(gl::UseProgram(0));
 
             // Reset buffer bindings to nothing.
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-            gl::BindVertexArray(0);
+            gl_call!(gl::BindBuffer(gl::ARRAY_BUFFER, 0));
+            gl_call!(gl::BindVertexArray(0));
         }
     }
 
@@ -210,8 +209,8 @@ impl RectRenderer {
 impl Drop for RectRenderer {
     fn drop(&mut self) {
         unsafe {
-            gl::DeleteBuffers(1, &self.vbo);
-            gl::DeleteVertexArrays(1, &self.vao);
+            gl_call!(gl::DeleteBuffers(1, &self.vbo));
+            gl_call!(gl::DeleteVertexArrays(1, &self.vao));
         }
     }
 }
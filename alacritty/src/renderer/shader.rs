@@ -1,7 +1,16 @@
-use std::fmt;
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fmt, fs, process};
+
+use log::debug;
 
 use crate::gl;
 use crate::gl::types::*;
+use crate::renderer::{discard_gl_errors, gl_call, gl_get_string, GlExtensions};
+
+/// Age after which unused program binaries are removed from the cache.
+const MAX_CACHE_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
 
 /// A wrapper for a shader program id, with automatic lifetime management.
 #[derive(Debug)]
@@ -33,28 +42,57 @@ impl ShaderProgram {
         vertex_shader: &'static str,
         fragment_shader: &'static str,
     ) -> Result<Self, ShaderError> {
+        // Skip compilation if the driver accepts a previously linked binary.
+        let cache =
+            ProgramCache::new(shader_version, shader_header, vertex_shader, fragment_shader);
+        if let Some(program) = cache.as_ref().and_then(ProgramCache::load) {
+            return Ok(program);
+        }
+
         let vertex_shader =
             Shader::new(shader_version, shader_header, gl::VERTEX_SHADER, vertex_shader)?;
         let fragment_shader =
             Shader::new(shader_version, shader_header, gl::FRAGMENT_SHADER, fragment_shader)?;
 
-        let program = unsafe { Self(gl::CreateProgram()) };
+        let program = unsafe { Self(gl_call!(gl::CreateProgram())) };
 
         let mut success: GLint = 0;
         unsafe {
-            gl::AttachShader(program.id(), vertex_shader.id());
-            gl::AttachShader(program.id(), fragment_shader.id());
-            gl::LinkProgram(program.id());
-            gl::GetProgramiv(program.id(), gl::LINK_STATUS, &mut success);
+            gl_call!(gl::AttachShader(program.id(), vertex_shader.id()));
+            gl_call!(gl::AttachShader(program.id(), fragment_shader.id()));
+            if cache.is_some() {
+                let hint = GLint::from(gl::TRUE);
+                gl_call!(gl::ProgramParameteri(
+                    program.id(),
+                    gl::PROGRAM_BINARY_RETRIEVABLE_HINT,
+                    hint,
+                ));
+            }
+            gl_call!(gl::LinkProgram(program.id()));
+            gl_call!(gl::GetProgramiv(program.id(), gl::LINK_STATUS, &mut success));
         }
 
         if success != i32::from(gl::TRUE) {
             return Err(ShaderError::Link(get_program_info_log(program.id())));
         }
 
+        if let Some(cache) = cache {
+            cache.store(&program);
+        }
+
         Ok(program)
     }
 
+    /// Get uniform location by name. Panic if failed.
+    pub fn get_uniform_location(&self, name: &'static CStr) -> Result<GLint, ShaderError> {
+        // This call doesn't require `UseProgram`.
+        let ret = unsafe { gl_call!(gl::GetUniformLocation(self.id(), name.as_ptr())) };
+        if ret == -1 {
+            return Err(ShaderError::Uniform(name));
+        }
+        Ok(ret)
+    }
+
     /// Get the shader program id.
     pub fn id(&self) -> GLuint {
         self.0
@@ -63,10 +101,204 @@ impl ShaderProgram {
 
 impl Drop for ShaderProgram {
     fn drop(&mut self) {
-        unsafe { gl::DeleteProgram(self.0) }
+        unsafe { gl_call!(gl::DeleteProgram(self.0)) }
     }
 }
 
+/// On-disk cache entry for a linked shader program binary.
+struct ProgramCache {
+    path: PathBuf,
+}
+
+impl ProgramCache {
+    /// Get the cache entry for a program.
+    ///
+    /// The entry is keyed by the driver vendor, renderer and version, together with the shader
+    /// sources, so driver updates or shader changes never load a stale binary. This returns `None`
+    /// when the driver doesn't support program binaries.
+    fn new(
+        shader_version: ShaderVersion,
+        shader_header: Option<&str>,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Option<Self> {
+        if !Self::is_supported() {
+            return None;
+        }
+
+        let vendor = gl_get_string(gl::VENDOR, "vendor").ok()?;
+        let renderer = gl_get_string(gl::RENDERER, "renderer").ok()?;
+        let version = gl_get_string(gl::VERSION, "OpenGL version").ok()?;
+
+        let key = stable_hash([
+            env!("VERSION"),
+            &vendor,
+            &renderer,
+            &version,
+            shader_version.shader_header(),
+            shader_header.unwrap_or_default(),
+            vertex_shader,
+            fragment_shader,
+        ]);
+        let path = cache_dir()?.join(format!("{key:016x}.bin"));
+
+        Some(Self { path })
+    }
+
+    /// Check if the driver can save and restore program binaries.
+    fn is_supported() -> bool {
+        if !gl::ProgramBinary::is_loaded() || !GlExtensions::contains("GL_ARB_get_program_binary") {
+            return false;
+        }
+
+        let mut formats: GLint = 0;
+        unsafe { gl_call!(gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats)) };
+        formats > 0
+    }
+
+    /// Load the cached program binary.
+    fn load(&self) -> Option<ShaderProgram> {
+        let data = fs::read(&self.path).ok()?;
+        if data.len() <= 4 {
+            return None;
+        }
+
+        // The binary is prefixed with its driver-specific format.
+        let (format, binary) = data.split_at(4);
+        let format = GLenum::from_le_bytes(format.try_into().ok()?);
+
+        let program = unsafe { ShaderProgram(gl_call!(gl::CreateProgram())) };
+
+        // Drivers reject binaries they can no longer use, which will just recompile the shaders.
+        //
+        // Unsupported formats raise an error, which must not be reported for the next GL call.
+        let mut success: GLint = 0;
+        unsafe {
+            gl::ProgramBinary(
+                program.id(),
+                format,
+                binary.as_ptr().cast(),
+                binary.len() as GLsizei,
+            );
+            if !discard_gl_errors() {
+                gl_call!(gl::GetProgramiv(program.id(), gl::LINK_STATUS, &mut success));
+            }
+        }
+
+        if success != GLint::from(gl::TRUE) {
+            debug!("Ignoring outdated shader cache {:?}", self.path);
+            return None;
+        }
+
+        Some(program)
+    }
+
+    /// Store the binary of a linked program.
+    fn store(&self, program: &ShaderProgram) {
+        let mut length: GLint = 0;
+        unsafe { gl_call!(gl::GetProgramiv(program.id(), gl::PROGRAM_BINARY_LENGTH, &mut length)) };
+        if length <= 0 {
+            return;
+        }
+
+        let mut binary = vec![0u8; length as usize];
+        let mut actual_length: GLsizei = 0;
+        let mut format: GLenum = 0;
+        unsafe {
+            gl_call!(gl::GetProgramBinary(
+                program.id(),
+                length,
+                &mut actual_length,
+                &mut format,
+                binary.as_mut_ptr().cast(),
+            ));
+        }
+        binary.truncate(actual_length.max(0) as usize);
+
+        let mut data = format.to_le_bytes().to_vec();
+        data.append(&mut binary);
+
+        // New binaries are only stored after driver or shader changes, which is when most of the
+        // existing entries become obsolete.
+        if let Some(cache_dir) = self.path.parent() {
+            evict_stale_entries(cache_dir);
+        }
+
+        // Write to a temporary file first, so other instances never load a partial binary.
+        let tmp_path = self.path.with_extension(format!("{}.tmp", process::id()));
+        let result = self
+            .path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp_path, data))
+            .and_then(|_| fs::rename(&tmp_path, &self.path));
+
+        if let Err(err) = result {
+            debug!("Unable to write shader cache {:?}: {}", self.path, err);
+            let _ = fs::remove_file(&tmp_path);
+        }
+    }
+}
+
+/// Remove all cache files which have not been written for [`MAX_CACHE_AGE`].
+///
+/// Binaries still in use are recompiled and stored again by the next launch.
+fn evict_stale_entries(cache_dir: &Path) {
+    let entries = match fs::read_dir(cache_dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let age = entry
+            .metadata()
+            .ok()
+            .filter(|metadata| metadata.is_file())
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| now.duration_since(modified).ok());
+
+        if age.is_some_and(|age| age > MAX_CACHE_AGE) {
+            debug!("Removing stale shader cache {:?}", entry.path());
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash of a list of strings.
+///
+/// Unlike the standard library hashers, the output is stable across Rust releases, which is
+/// required for keys persisted to disk.
+fn stable_hash<'a>(parts: impl IntoIterator<Item = &'a str>) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        // Prefix each part with its length, so moving bytes between parts changes the hash.
+        let length = (part.len() as u64).to_le_bytes();
+        for byte in length.iter().chain(part.as_bytes()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// Directory for cached program binaries.
+#[cfg(not(windows))]
+fn cache_dir() -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("alacritty")
+        .ok()
+        .map(|xdg| xdg.get_cache_home().join("shaders"))
+}
+
+/// Directory for cached program binaries.
+#[cfg(windows)]
+fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|path| path.join("alacritty").join("shaders"))
+}
+
 /// A wrapper for a shader id, with automatic lifetime management.
 #[derive(Debug)]
 struct Shader(GLuint);
@@ -92,18 +324,18 @@ impl Shader {
         sources.push(source.as_ptr().cast());
         lengths.push(source.len() as GLint);
 
-        let shader = unsafe { Self(gl::CreateShader(kind)) };
+        let shader = unsafe { Self(gl_call!(gl::CreateShader(kind))) };
 
         let mut success: GLint = 0;
         unsafe {
-            gl::ShaderSource(
+            gl_call!(gl::ShaderSource(
                 shader.id(),
                 lengths.len() as GLint,
                 sources.as_ptr().cast(),
                 lengths.as_ptr(),
-            );
-            gl::CompileShader(shader.id());
-            gl::GetShaderiv(shader.id(), gl::COMPILE_STATUS, &mut success);
+            ));
+            gl_call!(gl::CompileShader(shader.id()));
+            gl_call!(gl::GetShaderiv(shader.id(), gl::COMPILE_STATUS, &mut success));
         }
 
         if success == GLint::from(gl::TRUE) {
@@ -120,7 +352,7 @@ impl Shader {
 
 impl Drop for Shader {
     fn drop(&mut self) {
-        unsafe { gl::DeleteShader(self.0) }
+        unsafe { gl_call!(gl::DeleteShader(self.0)) }
     }
 }
 
@@ -128,14 +360,19 @@ fn get_program_info_log(program: GLuint) -> String {
     // Get expected log length.
     let mut max_length: GLint = 0;
     unsafe {
-        gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut max_length);
+        gl_call!(gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut max_length));
     }
 
     // Read the info log.
     let mut actual_length: GLint = 0;
     let mut buf: Vec<u8> = Vec::with_capacity(max_length as usize);
     unsafe {
-        gl::GetProgramInfoLog(program, max_length, &mut actual_length, buf.as_mut_ptr() as *mut _);
+        gl_call!(gl::GetProgramInfoLog(
+            program,
+            max_length,
+            &mut actual_length,
+            buf.as_mut_ptr() as *mut _
+        ));
     }
 
     // Build a string.
@@ -150,14 +387,19 @@ fn get_shader_info_log(shader: GLuint) -> String {
     // Get expected log length.
     let mut max_length: GLint = 0;
     unsafe {
-        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut max_length);
+        gl_call!(gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut max_length));
     }
 
     // Read the info log.
     let mut actual_length: GLint = 0;
     let mut buf: Vec<u8> = Vec::with_capacity(max_length as usize);
     unsafe {
-        gl::GetShaderInfoLog(shader, max_length, &mut actual_length, buf.as_mut_ptr() as *mut _);
+        gl_call!(gl::GetShaderInfoLog(
+            shader,
+            max_length,
+            &mut actual_length,
+            buf.as_mut_ptr() as *mut _
+        ));
     }
 
     // Build a string.
@@ -175,6 +417,9 @@ pub enum ShaderError {
 
     /// Error linking shader.
     Link(String),
+
+    /// Error getting uniform location.
+    Uniform(&'static CStr),
 }
 
 impl std::error::Error for ShaderError {}
@@ -184,6 +429,25 @@ impl fmt::Display for ShaderError {
         match self {
             Self::Compile(reason) => write!(f, "Failed compiling shader: {}", reason),
             Self::Link(reason) => write!(f, "Failed linking shader: {}", reason),
+            Self::Uniform(name) => write!(f, "Failed to get uniform location of {:?}", name),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_hash_is_fixed() {
+        // Changing these values invalidates every cached program binary.
+        assert_eq!(stable_hash([]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(["alacritty"]), 0xb326_0555_7b45_47fb);
+    }
+
+    #[test]
+    fn stable_hash_separates_parts() {
+        assert_eq!(stable_hash(["ab", "c"]), 0x7e60_470b_f599_cad6);
+        assert_eq!(stable_hash(["a", "bc"]), 0xba1e_1f0e_0704_d8ea);
+    }
+}
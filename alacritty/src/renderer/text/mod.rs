@@ -0,0 +1,88 @@
+use bitflags::bitflags;
+use crossfont::RasterizedGlyph;
+
+use crate::display::{Rgb, SizeInfo};
+use crate::gl;
+use crate::gl::types::*;
+use crate::renderer::gl_call;
+
+mod atlas;
+mod glsl3;
+pub mod glyph_cache;
+
+use atlas::Atlas;
+pub use glsl3::Glsl3Renderer;
+pub use glyph_cache::GlyphCache;
+use glyph_cache::{Glyph, LoadGlyph};
+
+// NOTE: These flags must be in sync with their usage in the text.*.glsl shaders.
+bitflags! {
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    struct RenderingGlyphFlags: u8 {
+        const COLORED   = 0b0000_0001;
+        const WIDE_CHAR = 0b0000_0010;
+    }
+}
+
+/// Rendering passes of the text shaders.
+#[repr(u8)]
+enum RenderingPass {
+    /// Rendering pass used to render background color in text shaders.
+    Background = 0,
+
+    /// The pass used to render the text itself.
+    SubpixelPass1 = 1,
+}
+
+/// Single cell of text which should be drawn.
+#[derive(Debug, Copy, Clone)]
+pub struct RenderableCell {
+    pub character: char,
+    pub line: usize,
+    pub column: usize,
+    pub fg: Rgb,
+    pub bg: Rgb,
+    pub bg_alpha: f32,
+}
+
+#[derive(Debug)]
+pub struct LoaderApi<'a> {
+    active_tex: &'a mut GLuint,
+    atlas: &'a mut Vec<Atlas>,
+    current_atlas: &'a mut usize,
+}
+
+impl<'a> LoadGlyph for LoaderApi<'a> {
+    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph {
+        Atlas::load_glyph(self.active_tex, self.atlas, self.current_atlas, rasterized)
+    }
+
+    fn clear(&mut self) {
+        Atlas::clear_atlas(self.atlas, self.current_atlas)
+    }
+}
+
+fn update_projection(u_projection: GLint, size: &SizeInfo) {
+    let width = size.width();
+    let height = size.height();
+    let padding_x = size.padding_x();
+    let padding_y = size.padding_y();
+
+    // Bounds check.
+    if (width as u32) < (2 * padding_x as u32) || (height as u32) < (2 * padding_y as u32) {
+        return;
+    }
+
+    // Compute scale and offset factors, from pixel to ndc space. Y is inverted.
+    //   [0, width - 2 * padding_x] to [-1, 1]
+    //   [height - 2 * padding_y, 0] to [-1, 1]
+    let scale_x = 2. / (width - 2. * padding_x);
+    let scale_y = -2. / (height - 2. * padding_y);
+    let offset_x = -1.;
+    let offset_y = 1.;
+
+    unsafe {
+        gl_call!(gl::Uniform4f(u_projection, offset_x, offset_y, scale_x, scale_y));
+    }
+}
@@ -84,4 +84,9 @@ impl Scheduler {
 
         self.timers.insert(index, Timer { interval, deadline, event, id: timer_id });
     }
+
+    /// Remove all timers scheduled for a window.
+    pub fn unschedule_window(&mut self, window_id: WindowId) {
+        self.timers.retain(|timer| timer.id.window_id != window_id);
+    }
 }
@@ -1,37 +1,73 @@
 //! Terminal window context.
 
 use std::error::Error;
+use std::mem;
+use std::rc::Rc;
 
+use glutin::config::Config as GlutinConfig;
+use glutin::display::GetGlDisplay;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use glutin::platform::x11::X11GlConfigExt;
+use log::info;
 use raw_window_handle::HasRawDisplayHandle;
 use winit::event::{Event as WinitEvent, WindowEvent};
-use winit::event_loop::{EventLoopProxy, EventLoopWindowTarget};
-use winit::window::WindowId;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{Theme, WindowId};
 
+#[cfg(unix)]
+use crate::cli::CaptureOptions;
+use crate::cli::{ParsedOptions, WindowOptions};
+use crate::config::{self, UiConfig, LOG_TARGET_CONFIG};
+#[cfg(unix)]
+use crate::display::capture::FrameCapture;
 use crate::display::window::Window;
 use crate::display::Display;
 use crate::event::{ActionContext, Event, InputProcessor};
-use crate::renderer;
+use crate::message_bar::MessageBuffer;
 use crate::scheduler::Scheduler;
+use crate::{geometry, renderer};
 
 /// Event context for one individual Alacritty window.
 pub struct WindowContext {
     pub display: Display,
     pub dirty: bool,
+    message_buffer: MessageBuffer,
     event_queue: Vec<WinitEvent<Event>>,
     occluded: bool,
+    config: Rc<UiConfig>,
+    startup_config: ParsedOptions,
+    window_config: ParsedOptions,
+    theme: Option<Theme>,
 }
 
 impl WindowContext {
     /// Create initial window context that does bootstrapping the graphics API we're going to use.
-    pub fn initial(event_loop: &EventLoopWindowTarget<Event>) -> Result<Self, Box<dyn Error>> {
+    pub fn initial(
+        event_loop: &EventLoopWindowTarget<Event>,
+        config: Rc<UiConfig>,
+        options: WindowOptions,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut startup_config = options.config_overrides();
+        let base_config = config.clone();
+        let mut config = startup_config.override_config_rc(config);
+
+        // Restore the geometry the window had when it was last closed.
+        //
+        // The geometry replaces the config file, but explicit CLI options take precedence.
+        if config.window.remember_geometry {
+            if let Some(geometry) = geometry::load(event_loop, startup_config.profile()) {
+                let mut restored = base_config;
+                geometry.apply(&mut Rc::make_mut(&mut restored).window);
+                config = startup_config.override_config_rc(restored);
+            }
+        }
+
         let raw_display_handle = event_loop.raw_display_handle();
 
         // Windows has different order of GL platform initialization compared to any other platform;
         // it requires the window first.
         #[cfg(windows)]
-        let window = Window::new(event_loop)?;
+        let window = Window::new(event_loop, &config)?;
         #[cfg(windows)]
         let raw_window_handle = Some(window.raw_window_handle());
 
@@ -45,6 +81,7 @@ impl WindowContext {
         #[cfg(not(windows))]
         let window = Window::new(
             event_loop,
+            &config,
             #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
             gl_config.x11_visual(),
         )?;
@@ -53,27 +90,79 @@ impl WindowContext {
         let gl_context =
             renderer::platform::create_gl_context(&gl_display, &gl_config, raw_window_handle)?;
 
-        let display = Display::new(window, gl_context, false)?;
+        let display = Display::new(window, gl_context, &config, false)?;
+
+        Self::new(display, config, startup_config)
+    }
+
+    /// Create additional context with the graphics platform other windows are using.
+    pub fn additional(
+        gl_config: &GlutinConfig,
+        event_loop: &EventLoopWindowTarget<Event>,
+        config: Rc<UiConfig>,
+        options: WindowOptions,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut startup_config = options.config_overrides();
+        let config = startup_config.override_config_rc(config);
+
+        let gl_display = gl_config.display();
+
+        // Create a display.
+        //
+        // The display manages a window and can draw the terminal.
+        let window = Window::new(
+            event_loop,
+            &config,
+            #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+            gl_config.x11_visual(),
+        )?;
+
+        // Create context.
+        let raw_window_handle = window.raw_window_handle();
+        let gl_context =
+            renderer::platform::create_gl_context(&gl_display, gl_config, Some(raw_window_handle))?;
+
+        let display = Display::new(window, gl_context, &config, false)?;
 
-        Self::new(display)
+        Self::new(display, config, startup_config)
     }
 
     /// Create a new terminal window context.
-    fn new(display: Display) -> Result<Self, Box<dyn Error>> {
+    fn new(
+        display: Display,
+        config: Rc<UiConfig>,
+        startup_config: ParsedOptions,
+    ) -> Result<Self, Box<dyn Error>> {
+        let id = u64::from(display.window.id());
+        info!("Created window with ID {}", id);
+
+        // Print the ID, so the window can be targeted through `alacritty msg --window-id`.
+        #[cfg(unix)]
+        if config.ipc_socket {
+            println!("Window ID: {id}");
+        }
+
         Ok(WindowContext {
             display,
+            config,
+            startup_config,
+            window_config: Default::default(),
+            message_buffer: Default::default(),
             event_queue: Default::default(),
             occluded: Default::default(),
             dirty: Default::default(),
+            theme: Default::default(),
         })
     }
 
     /// Draw the window.
-    pub fn draw(&mut self, scheduler: &mut Scheduler) {
+    ///
+    /// Returns `false` if no frame was presented, since the window is occluded.
+    pub fn draw(&mut self, scheduler: &mut Scheduler) -> bool {
         self.display.window.requested_redraw = false;
 
         if self.occluded {
-            return;
+            return false;
         }
 
         self.dirty = false;
@@ -81,17 +170,134 @@ impl WindowContext {
         // Force the display to process any pending display update.
         self.display.process_renderer_update();
 
-        self.display.draw(scheduler);
+        self.display.draw(scheduler, &self.message_buffer, &self.config);
+
+        true
+    }
+
+    /// Update the terminal window to the latest config.
+    pub fn update_config(&mut self, new_config: Rc<UiConfig>) {
+        // Apply the CLI options the window was created with and the IPC config overrides, using
+        // the colors for the system theme.
+        let (startup_config, window_config) = (&mut self.startup_config, &mut self.window_config);
+        let new_config = config::with_theme_colors(new_config, self.theme, |config| {
+            let config = startup_config.override_config_rc(config);
+            window_config.override_config_rc(config)
+        });
+
+        let old_config = mem::replace(&mut self.config, new_config);
+
+        // Update the window title when it was changed in the config.
+        if old_config.window.title != self.config.window.title {
+            self.display.window.set_title(&self.config.window.title);
+        }
+
+        // Apply changes to the scale factor override.
+        let detected_scale_factor = self.display.window.detected_scale_factor();
+        let scale_factor = self.config.window.scale_factor(detected_scale_factor);
+        let scale_factor_changed = scale_factor != self.display.window.scale_factor;
+        self.display.window.scale_factor = scale_factor;
+
+        // Update font size and cell dimensions.
+        if old_config.font != self.config.font || scale_factor_changed {
+            let scale_factor = scale_factor as f32;
+            let font_size = self.config.font.size().scale(scale_factor);
+            let font = self.config.font.clone().with_size(font_size);
+            self.display.pending_update.set_font(font);
+        }
+
+        // Update the cell layout when the padding was changed.
+        if old_config.window.padding(1.) != self.config.window.padding(1.)
+            || old_config.window.dynamic_padding != self.config.window.dynamic_padding
+            || scale_factor_changed
+        {
+            self.display.pending_update.dirty = true;
+        }
+
+        self.display.window.set_transparent(self.config.window_opacity() < 1.);
+
+        // Apply changes to the `Option` key handling.
+        #[cfg(target_os = "macos")]
+        if old_config.window.option_as_alt() != self.config.window.option_as_alt() {
+            self.display.window.set_option_as_alt(self.config.window.option_as_alt());
+        }
+
+        // Apply changes to secure keyboard entry.
+        #[cfg(target_os = "macos")]
+        {
+            let focused = self.display.window.has_focus();
+            let secure_keyboard_entry = self.config.window.secure_keyboard_entry;
+            self.display.window.update_secure_input(secure_keyboard_entry, focused);
+        }
+
+        // Apply the pending font change right away.
+        if self.display.pending_update.dirty {
+            Self::submit_display_update(&mut self.display, &self.message_buffer, &self.config);
+        }
+
+        // Redraw screen.
+        self.dirty = true;
+        if self.display.window.has_frame && !self.occluded {
+            self.display.window.request_redraw();
+        }
+    }
+
+    /// Update the system theme, switching to its colors.
+    pub fn set_theme(&mut self, theme: Option<Theme>, config: Rc<UiConfig>) {
+        if self.theme != theme {
+            self.theme = theme;
+            self.update_config(config);
+        }
+    }
+
+    /// Capture the next frames to PNG files.
+    #[cfg(unix)]
+    pub fn capture_frames(&mut self, options: &CaptureOptions) {
+        if options.count == 0 {
+            return;
+        }
+
+        info!("Capturing {} frames to {:?}", options.count, options.directory);
+
+        let directory = options.directory.clone();
+        let frame_capture = FrameCapture::new(directory, options.count, self.id());
+        self.display.frame_capture = Some(frame_capture);
+
+        // Draw a frame right away, so the capture doesn't wait for the next change.
+        self.dirty = true;
+        if self.display.window.has_frame && !self.occluded {
+            self.display.window.request_redraw();
+        }
+    }
+
+    /// Remove all messages about configuration errors.
+    pub fn clear_config_messages(&mut self) {
+        self.message_buffer.remove_target(LOG_TARGET_CONFIG);
+        self.display.pending_update.dirty = true;
+    }
+
+    /// Clear the window config overrides.
+    #[cfg(unix)]
+    pub fn reset_window_config(&mut self, config: Rc<UiConfig>) {
+        // Clear previous IPC overrides, keeping the options the window was created with.
+        self.window_config.clear();
+
+        // Reload current config to pull new IPC config.
+        self.update_config(config);
+    }
+
+    /// Add new window config overrides.
+    #[cfg(unix)]
+    pub fn add_window_config(&mut self, config: Rc<UiConfig>, options: &ParsedOptions) {
+        // Override config with CLI/IPC options.
+        self.window_config.extend_from_slice(options);
+
+        // Reload current config to pull new IPC config.
+        self.update_config(config);
     }
 
     /// Process events for this terminal window.
-    pub fn handle_event(
-        &mut self,
-        event_loop: &EventLoopWindowTarget<Event>,
-        event_proxy: &EventLoopProxy<Event>,
-        scheduler: &mut Scheduler,
-        event: WinitEvent<Event>,
-    ) {
+    pub fn handle_event(&mut self, event: WinitEvent<Event>) {
         match event {
             WinitEvent::AboutToWait
             | WinitEvent::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {
@@ -103,6 +309,11 @@ impl WindowContext {
                 // Continue to process all pending events.
             },
             event => {
+                #[cfg(unix)]
+                if let Some(frame_capture) = &mut self.display.frame_capture {
+                    frame_capture.record_event(&event);
+                }
+
                 self.event_queue.push(event);
                 return;
             },
@@ -110,11 +321,10 @@ impl WindowContext {
 
         let context = ActionContext {
             display: &mut self.display,
+            message_buffer: &mut self.message_buffer,
+            config: &self.config,
             dirty: &mut self.dirty,
             occluded: &mut self.occluded,
-            event_proxy,
-            event_loop,
-            scheduler,
         };
         let mut processor = InputProcessor::new(context);
 
@@ -124,7 +334,7 @@ impl WindowContext {
 
         // Process DisplayUpdate events.
         if self.display.pending_update.dirty {
-            Self::submit_display_update(&mut self.display);
+            Self::submit_display_update(&mut self.display, &self.message_buffer, &self.config);
             self.dirty = true;
         }
 
@@ -139,13 +349,24 @@ impl WindowContext {
         }
     }
 
+    /// Remember the window geometry for the next launch.
+    pub fn save_geometry(&self) {
+        if self.config.window.remember_geometry {
+            geometry::save(&self.display, self.startup_config.profile());
+        }
+    }
+
     /// ID of this terminal context.
     pub fn id(&self) -> WindowId {
         self.display.window.id()
     }
 
     /// Submit the pending changes to the `Display`.
-    fn submit_display_update(display: &mut Display) {
-        display.handle_update();
+    fn submit_display_update(
+        display: &mut Display,
+        message_buffer: &MessageBuffer,
+        config: &UiConfig,
+    ) {
+        display.handle_update(message_buffer, config);
     }
 }